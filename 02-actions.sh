@@ -85,15 +85,16 @@ xiond tx wasm execute $NFT_CONTRACT "$APPROVE_MSG" \
 
 
 #/////////////     NFT  List    ////////////////////////
+# The NFT is sent to the marketplace, msg is the base64 of {"set_listing":{"price":"10000"}}
 WALLET=xion1nmx9wtrkmdvfkrnrwkxc5uyduqa4l29wg3vd8e
 LIST_NFT='{
-  "ListNftForSale": {
-    "sender": "xion1nmx9wtrkmdvfkrnrwkxc5uyduqa4l29wg3vd8e",
+  "send_nft": {
+    "contract": "'$MARKETPLACE_CONTRACT'",
     "token_id": "0",
-    "msg": "eyJzZXRfbGlzdGluZyI6eyJvd25lciI6Inhpb24xbm14OXd0cmttZHZma3JucndreGM1dXlkdXFhNGwyOXdnM3ZkOGUiLCJjb2xsZWN0aW9uIjoieGlvbjE2cHhmem10Nmg5Zmo4ZXl3Z2NldzVlM2o1MmZqNHVhYTZ2cXpqeHYwZHU4eHM0NmZrZmpzbHo2eXNrIiwidG9rZW5faWQiOiIwIiwicHJpY2UiOiIxMDAwMCIsInJveWFsdHkiOiIxIn19"
+    "msg": "eyJzZXRfbGlzdGluZyI6eyJwcmljZSI6IjEwMDAwIn19"
   }
 }'
-xiond tx wasm execute $MARKETPLACE_CONTRACT "$LIST_NFT" \
+xiond tx wasm execute $NFT_CONTRACT "$LIST_NFT" \
   --from $WALLET \
   --gas-prices 0.025uxion \
   --gas auto \
@@ -1,23 +1,48 @@
 #[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env,
-    MessageInfo, Response, StdResult, Uint128, Uint64, WasmMsg,
+    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, Event, MessageInfo,
+    Order, Response, StdResult, Storage, Uint128, Uint64, WasmMsg,
 };
+use cw_storage_plus::{Bound, PrefixBound};
 
-use crate::msg::{AuctionListingHookMsg, ExecuteMsg, InstantiateMsg, ListingHookMsg, QueryMsg};
-use cosmwasm_std::{ensure, CosmosMsg, StdError};
-use cw2::set_contract_version;
-use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
-use cw_utils::must_pay;
+use crate::error::ContractError;
+use crate::migrations::MIGRATIONS;
+use crate::msg::{
+    ConfigResponse, Cw20HookMsg, Cw2981QueryMsg, Cw721HookMsg, EscrowInvariantResponse, ExecuteMsg,
+    FeeScheduleResponse, InstantiateMsg, MigrateMsg, OwnershipResponse, QueryMsg,
+    RoyaltiesInfoResponse,
+};
+use cosmwasm_std::{attr, ensure, Attribute, CosmosMsg, StdError};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
+use cw721::{Cw721ReceiveMsg, OwnerOfResponse};
+use cw_utils::{must_pay, Expiration};
+use semver::Version;
 
 use crate::state::{
-    Auction, AuctionStatus, Config, Deposits, Listing, State, ADMINS, AUCTIONS, CONFIG, DEPOSITS,
-    LISTINGS, STATE,
+    auctions, collection_offers, listings, offers, AntiSniping, Auction, AuctionExtension,
+    AuctionKind, AuctionStatus, Bundle, CollectionInfo, CollectionOffer, CollectionRoyalty, Config,
+    Deposits, Listing, Offer, OwnershipProposal, PriceCurve, State, TradingOperation, ADMINS,
+    AUCTION_ESCROW, BUNDLES, BUNDLE_SEQ, COLLECTIONS, COLLECTION_ROYALTIES, CONFIG, CW20_TOKENS,
+    DEPOSITS, NATIVE_OFFER_ESCROW, OWNERSHIP_PROPOSAL, PAUSE_STATE, STATE, TOTAL_AUCTION_ESCROW,
 };
 
 const CONTRACT_NAME: &str = "crates.io:marketplace";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Pagination limits for the enumeration queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+// Number of NFTs a bundle can hold
+const MAX_BUNDLE_ITEMS: usize = 30;
+// Basis points of a whole sale price
+const BPS_DENOMINATOR: u64 = 10_000;
+// Highest marketplace fee, 10% of the sale price
+const MAX_PROTOCOL_FEE_BPS: u64 = 1_000;
+// Longest anti-sniping window, extension or cap, 30 days
+const MAX_ANTI_SNIPING_SECONDS: u64 = 30 * 24 * 60 * 60;
+
 // Define the contract's entry points
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -25,40 +50,69 @@ pub fn instantiate(
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    validate_native_denom(&msg.native_denom)?;
+    validate_royalty(msg.royalty)?;
+    validate_protocol_fee(msg.protocol_fee_bps)?;
+    let owner = deps.api.addr_validate(&msg.owner)?;
+    let fee_collector = match msg.fee_collector {
+        Some(fee_collector) => deps.api.addr_validate(&fee_collector)?,
+        None => owner.clone(),
+    };
     let config = Config {
-        native_denom: msg.native_denom,
+        native_denom: msg.native_denom.clone(),
         royalty: msg.royalty,
+        protocol_fee_bps: msg.protocol_fee_bps,
+        fee_collector,
+        strict_collections: false,
+        anti_sniping: None,
     };
 
     let state = State {
-        owner: deps.api.addr_validate(&msg.owner)?,
+        owner: Some(owner),
         collection_fabric_address: deps.api.addr_validate(&msg.collection_fabric_address)?,
         listing_count: 0,
         auction_count: 0,
     };
     STATE.save(deps.storage, &state)?;
     CONFIG.save(deps.storage, &config)?;
+    for token in msg.cw20_tokens {
+        let token = deps.api.addr_validate(&token)?;
+        CW20_TOKENS.save(deps.storage, &token, &())?;
+    }
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new().add_attribute("method", "instantiate"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::ListNftForSale(cw721_msg) => list_nft_for_sale(deps, env, info, cw721_msg),
+        ExecuteMsg::ReceiveNft(cw721_msg) => receive_nft(deps, env, info, cw721_msg),
         ExecuteMsg::CancelListing {
             collection,
             token_id,
         } => cancel_listing(deps, env, info, collection, token_id),
+        ExecuteMsg::UpdateListingPrice {
+            collection,
+            token_id,
+            price,
+        } => update_listing_price(deps, env, info, collection, token_id, price),
+        ExecuteMsg::ReturnExpiredListings { limit } => {
+            return_expired_listings(deps, env, info, limit)
+        }
         ExecuteMsg::BuyNft {
             collection,
             token_id,
         } => buy_nft(deps, env, info, collection, token_id),
-        ExecuteMsg::BuyBatch { asks } => buy_batch(deps, env, info, asks),
-        ExecuteMsg::ListNftForAuction(cw721_msg) => {
-            list_nft_for_auction(deps, env, info, cw721_msg)
-        }
+        ExecuteMsg::BuyBatch {
+            asks,
+            allow_partial,
+        } => buy_batch(deps, env, info, asks, allow_partial),
         ExecuteMsg::StartAuction {
             collection,
             token_id,
@@ -76,13 +130,153 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             collection,
             token_id,
         } => claim_nft(deps, env, info, collection, token_id),
+        ExecuteMsg::BuyDutchAuction {
+            collection,
+            token_id,
+        } => buy_dutch_auction(deps, env, info, collection, token_id),
+        ExecuteMsg::BuyNow {
+            collection,
+            token_id,
+        } => buy_now(deps, env, info, collection, token_id),
         ExecuteMsg::AddAdmin { account_id } => add_admin(deps, env, info, account_id),
         ExecuteMsg::RemoveAdmin { account_id } => remove_admin(deps, env, info, account_id),
+        ExecuteMsg::Pause { operations } => set_paused(deps, env, info, operations, true),
+        ExecuteMsg::Unpause { operations } => set_paused(deps, env, info, operations, false),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            propose_new_owner(deps, env, info, owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => cancel_ownership_proposal(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => renounce_ownership(deps, env, info),
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::UpdateConfig {
+            native_denom,
+            royalty,
+            protocol_fee_bps,
+            fee_collector,
+            collection_fabric_address,
+            strict_collections,
+        } => update_config(
+            deps,
+            env,
+            info,
+            native_denom,
+            royalty,
+            protocol_fee_bps,
+            fee_collector,
+            collection_fabric_address,
+            strict_collections,
+        ),
+        ExecuteMsg::SetDefaultAntiSniping { anti_sniping } => {
+            set_default_anti_sniping(deps, env, info, anti_sniping)
+        }
+        ExecuteMsg::AddCw20Token { token } => add_cw20_token(deps, env, info, token),
+        ExecuteMsg::RemoveCw20Token { token } => remove_cw20_token(deps, env, info, token),
+        ExecuteMsg::SetCollectionRoyalty {
+            collection,
+            recipient,
+            royalty,
+        } => set_collection_royalty(deps, env, info, collection, recipient, royalty),
+        ExecuteMsg::RemoveCollectionRoyalty { collection } => {
+            remove_collection_royalty(deps, env, info, collection)
+        }
+        ExecuteMsg::RegisterCollection {
+            collection,
+            name,
+            creator,
+            verified,
+        } => register_collection(deps, env, info, collection, name, creator, verified),
+        ExecuteMsg::UnregisterCollection { collection } => {
+            unregister_collection(deps, env, info, collection)
+        }
+        ExecuteMsg::MakeOffer {
+            collection,
+            token_id,
+            price,
+            expires,
+        } => make_offer(deps, env, info, collection, token_id, price, expires),
+        ExecuteMsg::CancelOffer {
+            collection,
+            token_id,
+        } => cancel_offer(deps, env, info, collection, token_id),
+        ExecuteMsg::RetractOffer {
+            collection,
+            token_id,
+            bidder,
+        } => retract_offer(deps, env, info, collection, token_id, bidder),
+        ExecuteMsg::AcceptOffer {
+            collection,
+            token_id,
+            bidder,
+        } => accept_offer(deps, env, info, collection, token_id, bidder),
+        ExecuteMsg::MakeCollectionOffer {
+            collection,
+            price,
+            quantity,
+            expires,
+        } => make_collection_offer(deps, env, info, collection, price, quantity, expires),
+        ExecuteMsg::CancelCollectionOffer { collection } => {
+            cancel_collection_offer(deps, env, info, collection)
+        }
+        ExecuteMsg::AcceptCollectionOffer {
+            collection,
+            token_id,
+            bidder,
+        } => accept_collection_offer(deps, env, info, collection, token_id, bidder),
+        ExecuteMsg::ListBundle {
+            items,
+            price,
+            denom,
+        } => list_bundle(deps, env, info, items, price, denom),
+        ExecuteMsg::CancelBundle { bundle_id } => cancel_bundle(deps, env, info, bundle_id),
+        ExecuteMsg::BuyBundle { bundle_id } => buy_bundle(deps, env, info, bundle_id),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    ensure!(
+        stored.contract == CONTRACT_NAME,
+        ContractError::InvalidContractName {
+            name: stored.contract
+        }
+    );
+    let stored_version = parse_version(&stored.version)?;
+    let version = parse_version(CONTRACT_VERSION)?;
+    ensure!(
+        stored_version <= version,
+        ContractError::CannotMigrateDowngrade {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string()
+        }
+    );
+
+    // Run every step newer than the stored version, in order
+    for migration in MIGRATIONS {
+        let step_version = parse_version(migration.version)?;
+        if stored_version < step_version && step_version <= version {
+            (migration.run)(deps.storage)?;
+        }
     }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    version
+        .parse()
+        .map_err(|_| ContractError::InvalidContractVersion {
+            version: version.to_string(),
+        })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetListingCount {} => to_json_binary(&query_listing_count(deps)?),
         QueryMsg::GetListingByCollectionTokenID {
@@ -90,49 +284,237 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             token_id,
         } => to_json_binary(&query_listing_by_index(deps, collection, token_id)?),
         QueryMsg::GetAuctionCount {} => to_json_binary(&query_auction_count(deps)?),
+        QueryMsg::GetAuctionEscrow {
+            collection,
+            token_id,
+        } => to_json_binary(
+            &AUCTION_ESCROW
+                .may_load(deps.storage, (&collection, &token_id))?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::GetEscrowInvariant {} => to_json_binary(&query_escrow_invariant(deps, env)?),
+        QueryMsg::GetCurrentDutchPrice {
+            collection,
+            token_id,
+        } => to_json_binary(&query_current_dutch_price(deps, env, collection, token_id)?),
         QueryMsg::GetAuctionByCollectionTokenID {
             collection,
             token_id,
-        } => to_json_binary(&query_auction_by_index(deps, collection, token_id)?),
+        } => to_json_binary(&query_auction_by_index(deps, env, collection, token_id)?),
         QueryMsg::IsAdmin { account_id } => to_json_binary(&query_is_admin(deps, account_id)?),
+        QueryMsg::GetOwnership {} => to_json_binary(&query_ownership(deps)?),
+        QueryMsg::GetPauseState {} => {
+            to_json_binary(&PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default())
+        }
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::GetCw20Tokens {} => to_json_binary(&query_cw20_tokens(deps)?),
+        QueryMsg::GetFeeSchedule {} => to_json_binary(&query_fee_schedule(deps)?),
+        QueryMsg::GetCollectionRoyalty { collection } => {
+            to_json_binary(&COLLECTION_ROYALTIES.may_load(deps.storage, &collection)?)
+        }
+        QueryMsg::GetCollection { collection } => {
+            to_json_binary(&query_collection(deps, collection)?)
+        }
+        QueryMsg::ListCollections {
+            verified_only,
+            start_after,
+            limit,
+        } => to_json_binary(&query_list_collections(
+            deps,
+            verified_only,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetRoyaltyInfo {
+            collection,
+            token_id,
+            sale_price,
+        } => to_json_binary(&query_royalty(deps, &collection, &token_id, sale_price)?),
+        QueryMsg::GetOffersByToken {
+            collection,
+            token_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_offers_by_token(
+            deps,
+            collection,
+            token_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetOffersByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_json_binary(&query_offers_by_bidder(deps, bidder, start_after, limit)?),
+        QueryMsg::GetBestCollectionOffer { collection, denom } => {
+            to_json_binary(&query_best_collection_offer(deps, env, collection, denom)?)
+        }
+        QueryMsg::ListListings {
+            collection,
+            start_after,
+            limit,
+        } => to_json_binary(&query_list_listings(deps, collection, start_after, limit)?),
+        QueryMsg::ListAuctions {
+            collection,
+            status,
+            start_after,
+            limit,
+        } => to_json_binary(&query_list_auctions(
+            deps,
+            env,
+            collection,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListingsBySeller {
+            seller,
+            start_after,
+            limit,
+        } => to_json_binary(&query_listings_by_seller(deps, seller, start_after, limit)?),
+        QueryMsg::PrivateListings {
+            buyer,
+            start_after,
+            limit,
+        } => to_json_binary(&query_private_listings(deps, buyer, start_after, limit)?),
+        QueryMsg::GetBundle { bundle_id } => to_json_binary(&query_bundle(deps, bundle_id)?),
+        QueryMsg::ListBundles { start_after, limit } => {
+            to_json_binary(&query_list_bundles(deps, start_after, limit)?)
+        }
+        QueryMsg::AuctionsBySeller {
+            seller,
+            start_after,
+            limit,
+        } => to_json_binary(&query_auctions_by_seller(
+            deps,
+            env,
+            seller,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AuctionsByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_json_binary(&query_auctions_by_bidder(
+            deps,
+            env,
+            bidder,
+            start_after,
+            limit,
+        )?),
     }
 }
 
 // Implement the contract's logic
-pub fn list_nft_for_sale(
+/// Handles an NFT sent with `SendNft`. The NFT is already held by the marketplace, so the
+/// collection is the caller and the seller is whoever sent it.
+pub fn receive_nft(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     cw721_msg: Cw721ReceiveMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Listing)?;
+    let collection = info.sender.into_string();
+    ensure_collection_allowed(deps.as_ref(), &collection)?;
+    let seller = deps.api.addr_validate(&cw721_msg.sender)?.into_string();
+    let token_id = cw721_msg.token_id;
+    ensure_not_listed(deps.as_ref(), &collection, &token_id)?;
+
     match from_json(&cw721_msg.msg) {
-        Ok(ListingHookMsg::SetListing {
-            owner,
+        Ok(Cw721HookMsg::SetListing {
+            price,
+            denom,
+            expires_at,
+            reserved_for,
+        }) => execute_list_nft_for_sale(
+            deps,
+            env,
+            seller,
             collection,
             token_id,
             price,
-            royalty,
-        }) => {
-            execute_list_nft_for_sale(deps, env, info, owner, collection, token_id, price, royalty)
-        }
-        _ => Err(StdError::generic_err("Invalid ListingHookMsg")),
+            denom,
+            expires_at,
+            reserved_for,
+        ),
+        Ok(Cw721HookMsg::SetAuctionListing {
+            start_price,
+            min_bid_step,
+            start_time,
+            end_time,
+            anti_sniping,
+            reserve_price,
+            hide_reserve_price,
+            buy_now_price,
+        }) => execute_list_nft_for_auction(
+            deps,
+            env,
+            seller,
+            collection,
+            token_id,
+            start_price,
+            min_bid_step,
+            start_time,
+            end_time,
+            anti_sniping,
+            reserve_price,
+            hide_reserve_price,
+            buy_now_price,
+        ),
+        Ok(Cw721HookMsg::SetDutchAuctionListing {
+            start_price,
+            floor_price,
+            start_time,
+            end_time,
+            curve,
+        }) => execute_list_nft_for_dutch_auction(
+            deps,
+            env,
+            seller,
+            collection,
+            token_id,
+            start_price,
+            floor_price,
+            start_time,
+            end_time,
+            curve,
+        ),
+        Err(_) => Err(ContractError::InvalidHookMsg),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn execute_list_nft_for_sale(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
     owner: String,
     collection: String,
     token_id: String,
     price: Uint128,
-    royalty: Uint128,
-) -> StdResult<Response> {
+    denom: Option<Denom>,
+    expires_at: Option<Uint64>,
+    reserved_for: Option<String>,
+) -> Result<Response, ContractError> {
     // Check if the NFT is already listed
-    if DEPOSITS.has(deps.storage, (&collection, &owner, &token_id)) == true {
-        return Err(StdError::generic_err("NFT is already listed"));
+    if DEPOSITS.has(deps.storage, (&collection, &owner, &token_id)) {
+        return Err(ContractError::AlreadyListed);
     }
+    ensure!(!price.is_zero(), ContractError::ZeroPrice);
+    if let Some(expires_at) = expires_at {
+        ensure!(
+            expires_at > Uint64::from(env.block.time.seconds()),
+            ContractError::InvalidExpiration
+        );
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let denom = validate_denom(deps.as_ref(), &config, denom)?;
+    let reserved_for = reserved_for
+        .map(|buyer| deps.api.addr_validate(&buyer))
+        .transpose()?;
 
     let deposit = Deposits {
         owner: owner.clone(),
@@ -145,31 +527,20 @@ fn execute_list_nft_for_sale(
         seller: owner.clone(),
         collection: collection.clone(),
         token_id: token_id.clone(),
-        price: price.clone(),
-        royalty: royalty.clone(),
+        price,
+        denom: denom.clone(),
+        expires_at,
+        reserved_for,
     };
     let mut state = STATE.load(deps.storage)?;
     state.listing_count += 1;
-    LISTINGS.save(deps.storage, (&collection, &token_id), &listing)?;
+    listings().save(deps.storage, (&collection, &token_id), &listing)?;
     STATE.save(deps.storage, &state)?;
 
-    // Transfer the NFT from the seller to the marketplace contract
-    let transfer_to_marketplace_msg = Cw721ExecuteMsg::TransferNft {
-        recipient: env.contract.address.to_string(), // Marketplace contract address
-        token_id: token_id.clone(),
-    };
-
-    let execute_transfer_to_marketplace = WasmMsg::Execute {
-        contract_addr: collection.to_string(),
-        msg: to_json_binary(&transfer_to_marketplace_msg)?,
-        funds: vec![],
-    };
-
-    // Return the response with the approval message
     Ok(Response::new()
-        .add_message(execute_transfer_to_marketplace)
         .add_attribute("method", "list_nft_for_sale")
-        .add_attribute("listing_id", state.listing_count.to_string()))
+        .add_attribute("listing_id", state.listing_count.to_string())
+        .add_attribute("denom", denom_to_string(&denom)))
 }
 
 pub fn cancel_listing(
@@ -178,17 +549,16 @@ pub fn cancel_listing(
     info: MessageInfo,
     collection: String,
     token_id: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let owner = info.sender.clone().into_string();
-    // Load the listing from storage
-    if DEPOSITS.has(deps.storage, (&collection, &owner, &token_id)) == false {
-        return Err(StdError::generic_err(
-            "Only the owner can cancel the listing",
-        ));
-    }
+    // Load the listing from storage, bundle items and auctions are cancelled on their own
+    let listing = listings()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::NotListed)?;
+    ensure!(listing.seller == owner, ContractError::Unauthorized);
 
     DEPOSITS.remove(deps.storage, (&collection, &owner, &token_id));
-    LISTINGS.remove(deps.storage, (&collection, &token_id));
+    listings().remove(deps.storage, (&collection, &token_id))?;
     // Transfer the NFT from the seller to the marketplace contract
     let transfer_from_marketplace_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
         recipient: owner, // Marketplace contract address
@@ -212,240 +582,615 @@ pub fn cancel_listing(
         .add_attribute("method", "cancel_listing"))
 }
 
-fn buy_nft(
+pub fn update_listing_price(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     collection: String,
     token_id: String,
-) -> StdResult<Response> {
-    let config = CONFIG.load(deps.storage)?;
-    let buyer = info.sender.to_string();
-    let funds_sent = must_pay(&info, &config.native_denom).map_err(|e| StdError::generic_err(e.to_string()))?;
-    // Load the listing
-    let listing = LISTINGS.may_load(deps.storage, (&collection, &token_id))?;
-    let state = STATE.load(deps.storage)?;
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Listing)?;
+    let seller = info.sender.into_string();
+    if !DEPOSITS.has(deps.storage, (&collection, &seller, &token_id)) {
+        return Err(ContractError::Unauthorized);
+    }
+    ensure!(!price.is_zero(), ContractError::ZeroPrice);
 
-    match listing {
-        Some(listing) => {
-            // Ensure the buyer is not the seller
-            ensure!(
-                info.sender != listing.seller,
-                StdError::generic_err("You cannot buy your own NFT")
-            );
-            // Calculate the royalty amount
-            let royalty_amount = listing.price.multiply_ratio(listing.royalty, 100u128); // royalty = (listing.price * config.royalty) / 100
+    let mut listing = listings()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::NotListed)?;
+    let old_price = listing.price;
+    listing.price = price;
+    listings().save(deps.storage, (&collection, &token_id), &listing)?;
 
-            if funds_sent != listing.price {
-                Err(StdError::generic_err("Invalid amount"))
-            } else {
-                // Transfer the NFT from the seller to the buyer
-                let transfer_to_buyer_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
-                    recipient: buyer.clone(), // Buyer
-                    token_id: listing.token_id,
-                };
+    let event = Event::new("listing_price_updated")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", seller)
+        .add_attribute("old_price", old_price.to_string())
+        .add_attribute("new_price", price.to_string());
 
-                let execute_transfer_to_buyer = WasmMsg::Execute {
-                    contract_addr: collection.clone(), // NFT contract address
-                    msg: to_json_binary(&transfer_to_buyer_msg)?,
-                    funds: vec![],
-                };
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("method", "update_listing_price"))
+}
 
-                // Transfer funds from the buyer to the seller
-                let transfer_funds_to_seller = BankMsg::Send {
-                    to_address: listing.seller.to_string(), // Seller
-                    amount: vec![Coin {
-                        denom: config.native_denom.to_string(), // Replace with your native token denom
-                        amount: listing.price - royalty_amount, // Seller receives the listing price
-                    }],
-                };
+/// Sends expired listings back to their sellers, callable by anyone.
+pub fn return_expired_listings(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let now = env.block.time.seconds();
 
-                // Transfer royalty to the marketplace contract
-                let transfer_royalty_to_marketplace = BankMsg::Send {
-                    to_address: state.collection_fabric_address.to_string(), // Marketplace contract
-                    amount: vec![Coin {
-                        denom: config.native_denom.to_string(), // Replace with your native token denom
-                        amount: royalty_amount,                 // Marketplace receives the royalty
-                    }],
-                };
+    // Listings without expiry are indexed at u64::MAX, so they never match
+    let expired = listings()
+        .idx
+        .expiry
+        .prefix_range_raw(
+            deps.storage,
+            None,
+            Some(PrefixBound::inclusive(now)),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, listing)| listing))
+        .collect::<StdResult<Vec<Listing>>>()?;
 
-                // Remove the listing
-                DEPOSITS.remove(
-                    deps.storage,
-                    (&collection, &listing.seller.to_string(), &token_id),
-                );
-                LISTINGS.remove(deps.storage, (&collection, &token_id));
-
-                // Return the response with the transfer messages
-                Ok(Response::new()
-                    .add_message(execute_transfer_to_buyer) // Transfer NFT
-                    .add_message(transfer_funds_to_seller) // Transfer funds to seller
-                    .add_message(transfer_royalty_to_marketplace) // Transfer royalty to marketplace
-                    .add_attribute("method", "buy_nft")
-                    .add_attribute("buyer", info.sender.into_string())
-                    .add_attribute("seller", listing.seller.to_string()))
-            }
-        }
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for listing in &expired {
+        DEPOSITS.remove(
+            deps.storage,
+            (&listing.collection, &listing.seller, &listing.token_id),
+        );
+        listings().remove(deps.storage, (&listing.collection, &listing.token_id))?;
+        messages.push(transfer_nft_msg(
+            &listing.collection,
+            &listing.seller,
+            &listing.token_id,
+        )?);
+    }
+
+    let mut state = STATE.load(deps.storage)?;
+    state.listing_count = state.listing_count.saturating_sub(expired.len() as u128);
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "return_expired_listings")
+        .add_attribute("returned", expired.len().to_string()))
+}
+
+fn is_listing_expired(env: &Env, listing: &Listing) -> bool {
+    listing
+        .expires_at
+        .is_some_and(|expires_at| Uint64::from(env.block.time.seconds()) >= expires_at)
+}
+
+/// Private listings can only be bought by the address they are reserved for.
+fn is_listing_open_to(listing: &Listing, buyer: &Addr) -> bool {
+    match &listing.reserved_for {
+        Some(reserved_for) => reserved_for == buyer,
+        None => true,
     }
 }
 
+fn buy_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    execute_buy_nft(
+        deps,
+        env,
+        info.sender,
+        collection,
+        token_id,
+        Denom::Native(config.native_denom),
+        funds_sent,
+    )
+}
+
+fn execute_buy_nft(
+    deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    collection: String,
+    token_id: String,
+    denom: Denom,
+    funds_sent: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    // Load the listing
+    let listing = listings()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::NotListed)?;
+    ensure!(
+        funds_sent == listing.price,
+        ContractError::InvalidFunds {
+            expected: listing.price,
+            received: funds_sent
+        }
+    );
+
+    let (messages, event) = settle_listing(deps, &env, &buyer, &denom, &listing)?;
+
+    // Return the response with the transfer messages
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_event(event)
+        .add_attribute("method", "buy_nft")
+        .add_attribute("buyer", buyer.into_string())
+        .add_attribute("seller", listing.seller)
+        .add_attribute("denom", denom_to_string(&listing.denom)))
+}
+
 fn buy_batch(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     asks: Vec<(String, String)>,
-) -> StdResult<Response> {
+    allow_partial: bool,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let buyer = info.sender.to_string();
-    let funds_sent = must_pay(&info, &config.native_denom).unwrap();
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    execute_buy_batch(
+        deps,
+        env,
+        info.sender,
+        asks,
+        allow_partial,
+        Denom::Native(config.native_denom),
+        funds_sent,
+    )
+}
 
-    let mut messages: Vec<WasmMsg> = vec![];
+fn execute_buy_batch(
+    mut deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    asks: Vec<(String, String)>,
+    allow_partial: bool,
+    denom: Denom,
+    funds_sent: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
     let mut total_price = Uint128::zero();
-    let mut sellers: Vec<(String, Uint128)> = vec![];
-
-    for ask in &asks {
-        // Load the listing from storage
-        let listing = LISTINGS.may_load(deps.storage, (&ask.0, &ask.1))?;
-
-        // Use `match` or `if let` to handle the `Option` returned by `may_load`
-        match listing {
-            Some(listing) => {
-                // Ensure the buyer is not the seller
-                ensure!(
-                    info.sender != listing.seller,
-                    StdError::generic_err("You cannot buy your own NFT")
-                );
+    let mut skipped = 0u32;
 
-                // Add the listing price to the total price
+    for (collection, token_id) in &asks {
+        match listings().may_load(deps.storage, (collection, token_id))? {
+            Some(listing) if !allow_partial || !is_listing_expired(&env, &listing) => {
                 total_price += listing.price;
-                sellers.push((listing.seller, listing.price));
-            }
-            None => {
-                // Return an error if the token is not listed for sale
-                return Err(StdError::generic_err("TokenNotListedForSale"));
+                let (sale_msgs, event) =
+                    settle_listing(deps.branch(), &env, &buyer, &denom, &listing)?;
+                messages.extend(sale_msgs);
+                events.push(event);
             }
+            // In partial mode, listings that are gone are skipped and refunded
+            _ if allow_partial => skipped += 1,
+            _ => return Err(ContractError::NotListed),
         }
     }
 
-    if funds_sent != total_price {
-        Err(StdError::generic_err("Invalid amount"))
+    let refund = if allow_partial {
+        ensure!(!events.is_empty(), ContractError::NotListed);
+        funds_sent
+            .checked_sub(total_price)
+            .map_err(|_| ContractError::InvalidFunds {
+                expected: total_price,
+                received: funds_sent,
+            })?
     } else {
-        for ask in &asks {
-            let listing = LISTINGS.load(deps.storage, (&ask.0, &ask.1))?;
-
-            // Transfer the NFT to the buyer
-            let transfer_to_buyer_msg = WasmMsg::Execute {
-                contract_addr: listing.collection.to_string(),
-                msg: to_json_binary(&nft::contract::Cw721ExecuteMsg::TransferNft {
-                    recipient: buyer.clone(),
-                    token_id: listing.token_id.clone(),
-                })?,
-                funds: vec![],
-            };
-            messages.push(transfer_to_buyer_msg);
-
-            // Remove the listing
-            DEPOSITS.remove(deps.storage, (&ask.0, &listing.seller.to_string(), &ask.1));
-            LISTINGS.remove(deps.storage, (&ask.0, &ask.1));
-        }
-
-        // Create a list of BankMsg transfers
-        let fund_transfers: Vec<BankMsg> = sellers
-            .into_iter()
-            .map(|(seller, amount)| BankMsg::Send {
-                to_address: seller,
-                amount: vec![Coin {
-                    denom: config.native_denom.to_string(),
-                    amount,
-                }],
-            })
-            .collect();
-
-        Ok(Response::new()
-            .add_messages(messages)
-            .add_messages(fund_transfers)
-            .add_attribute("method", "buy_batch")
-            .add_attribute("buyer", info.sender.into_string()))
+        ensure!(
+            funds_sent == total_price,
+            ContractError::InvalidFunds {
+                expected: total_price,
+                received: funds_sent
+            }
+        );
+        Uint128::zero()
+    };
+    if !refund.is_zero() {
+        messages.push(payment_msg(&denom, buyer.as_str(), refund)?);
     }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(events)
+        .add_attribute("method", "buy_batch")
+        .add_attribute("buyer", buyer.into_string())
+        .add_attribute("denom", denom_to_string(&denom))
+        .add_attribute("skipped", skipped.to_string())
+        .add_attribute("refund", refund.to_string()))
+}
+
+/// Checks that `buyer` can buy the listing with `denom`, removes it and returns the messages
+/// transferring the NFT and paying the seller and the royalty.
+fn settle_listing(
+    deps: DepsMut,
+    env: &Env,
+    buyer: &Addr,
+    denom: &Denom,
+    listing: &Listing,
+) -> Result<(Vec<CosmosMsg>, Event), ContractError> {
+    // Ensure the buyer is not the seller
+    ensure!(*buyer != listing.seller, ContractError::CannotBuyOwnNft);
+    ensure!(*denom == listing.denom, ContractError::InvalidDenom);
+    ensure!(
+        !is_listing_expired(env, listing),
+        ContractError::ListingExpired
+    );
+    ensure!(
+        is_listing_open_to(listing, buyer),
+        ContractError::ListingReserved
+    );
+
+    // Remove the listing
+    DEPOSITS.remove(
+        deps.storage,
+        (&listing.collection, &listing.seller, &listing.token_id),
+    );
+    listings().remove(deps.storage, (&listing.collection, &listing.token_id))?;
+    let mut state = STATE.load(deps.storage)?;
+    state.listing_count = state.listing_count.saturating_sub(1);
+    STATE.save(deps.storage, &state)?;
+
+    // Transfer the NFT to the buyer, then pay the seller and the royalty
+    let mut messages = vec![transfer_nft_msg(
+        &listing.collection,
+        buyer.as_str(),
+        &listing.token_id,
+    )?];
+    let payout = sale_payout(
+        deps.as_ref(),
+        &listing.denom,
+        &listing.collection,
+        &listing.token_id,
+        &listing.seller,
+        listing.price,
+    )?;
+
+    let event = Event::new("sale")
+        .add_attribute("collection", &listing.collection)
+        .add_attribute("token_id", &listing.token_id)
+        .add_attribute("seller", &listing.seller)
+        .add_attribute("buyer", buyer.as_str())
+        .add_attribute("price", listing.price.to_string())
+        .add_attribute("denom", denom_to_string(&listing.denom))
+        .add_attributes(payout.attributes());
+    messages.extend(payout.messages);
+
+    Ok((messages, event))
 }
 
-pub fn list_nft_for_auction(
+fn receive_cw20(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    cw721_msg: Cw721ReceiveMsg,
-) -> StdResult<Response> {
-    match from_json(&cw721_msg.msg) {
-        Ok(AuctionListingHookMsg::SetAuctionListing {
-            owner,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The sender of a Receive message is the CW20 contract itself
+    if !CW20_TOKENS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Cw20TokenNotAccepted);
+    }
+    let buyer = deps.api.addr_validate(&cw20_msg.sender)?;
+    let denom = Denom::Cw20(info.sender);
+
+    match from_json(&cw20_msg.msg) {
+        Ok(Cw20HookMsg::BuyNft {
             collection,
             token_id,
-            start_price,
-            start_time,
-            end_time,
-            min_bid_step,
-            royalty,
-        }) => execute_list_nft_for_auction(
+        }) => execute_buy_nft(
             deps,
             env,
-            info,
-            owner,
+            buyer,
             collection,
             token_id,
-            start_price,
-            min_bid_step,
-            start_time,
-            end_time,
-            royalty,
+            denom,
+            cw20_msg.amount,
+        ),
+        Ok(Cw20HookMsg::BuyBatch {
+            asks,
+            allow_partial,
+        }) => execute_buy_batch(
+            deps,
+            env,
+            buyer,
+            asks,
+            allow_partial,
+            denom,
+            cw20_msg.amount,
+        ),
+        Ok(Cw20HookMsg::BuyBundle { bundle_id }) => {
+            execute_buy_bundle(deps, env, buyer, bundle_id, denom, cw20_msg.amount)
+        }
+        Ok(Cw20HookMsg::MakeOffer {
+            collection,
+            token_id,
+            expires,
+        }) => execute_make_offer(
+            deps,
+            env,
+            buyer,
+            collection,
+            token_id,
+            denom,
+            cw20_msg.amount,
+            expires,
+        ),
+        Ok(Cw20HookMsg::MakeCollectionOffer {
+            collection,
+            price,
+            quantity,
+            expires,
+        }) => execute_make_collection_offer(
+            deps,
+            env,
+            buyer,
+            collection,
+            denom,
+            price,
+            quantity,
+            cw20_msg.amount,
+            expires,
         ),
-        _ => Err(StdError::generic_err("Invalid AuctionListingHookMsg")),
+        _ => Err(ContractError::InvalidHookMsg),
     }
 }
 
-pub fn execute_list_nft_for_auction(
-    deps: DepsMut,
-    env: Env,
-    _info: MessageInfo,
-    owner: String,
-    collection: String,
-    token_id: String,
-    start_price: Uint128,
-    min_bid_step: Uint128,
-    start_time: Uint64,
-    end_time: Uint64,
-    royalty: Uint128,
-) -> StdResult<Response> {
-    // Check if the caller is the NFT owner
-    let nft_owner: Addr = deps.querier.query_wasm_smart(
-        collection.clone(),
+/// Builds the message paying `amount` of `denom` from the marketplace to `recipient`.
+fn payment_msg(denom: &Denom, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match denom {
+        Denom::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.to_string(),
+                amount,
+            }],
+        }
+        .into(),
+        Denom::Cw20(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+    Ok(msg)
+}
+
+/// Payment messages of a sale, with the marketplace fee and the creator royalty taken from it.
+struct SalePayout {
+    messages: Vec<CosmosMsg>,
+    protocol_fee: Uint128,
+    royalty: RoyaltiesInfoResponse,
+}
+
+impl SalePayout {
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            attr("protocol_fee", self.protocol_fee.to_string()),
+            attr("royalty", self.royalty.royalty_amount.to_string()),
+            attr("royalty_recipient", &self.royalty.address),
+        ]
+    }
+}
+
+/// Splits the sale `price` of a token into the seller's earnings, the marketplace fee paid to
+/// the fee collector and the royalty owed to the creator.
+fn sale_payout(
+    deps: Deps,
+    denom: &Denom,
+    collection: &str,
+    token_id: &str,
+    seller: &str,
+    price: Uint128,
+) -> StdResult<SalePayout> {
+    let config = CONFIG.load(deps.storage)?;
+    let protocol_fee = protocol_fee(&config, price);
+    let mut royalty = query_royalty(deps, collection, token_id, price)?;
+    // The royalty never takes more than what is left after the fee, so a sale can always settle
+    let after_fee = price.checked_sub(protocol_fee)?;
+    royalty.royalty_amount = royalty.royalty_amount.min(after_fee);
+    let earnings = after_fee.checked_sub(royalty.royalty_amount)?;
+
+    // Zero amount transfers are rejected by the bank module, skip them
+    let mut messages = vec![];
+    if !earnings.is_zero() {
+        messages.push(payment_msg(denom, seller, earnings)?);
+    }
+    if !protocol_fee.is_zero() {
+        messages.push(payment_msg(
+            denom,
+            config.fee_collector.as_str(),
+            protocol_fee,
+        )?);
+    }
+    if !royalty.royalty_amount.is_zero() {
+        messages.push(payment_msg(
+            denom,
+            &royalty.address,
+            royalty.royalty_amount,
+        )?);
+    }
+    Ok(SalePayout {
+        messages,
+        protocol_fee,
+        royalty,
+    })
+}
+
+fn protocol_fee(config: &Config, price: Uint128) -> Uint128 {
+    price.multiply_ratio(config.protocol_fee_bps, BPS_DENOMINATOR)
+}
+
+/// Royalty owed on a sale, taken from the collection's CW-2981 `RoyaltyInfo`. Collections that
+/// don't implement it fall back to their default set on the marketplace, then to the marketplace
+/// royalty paid to the collection fabric.
+fn query_royalty(
+    deps: Deps,
+    collection: &str,
+    token_id: &str,
+    sale_price: Uint128,
+) -> StdResult<RoyaltiesInfoResponse> {
+    let query = Cw2981QueryMsg::RoyaltyInfo {
+        token_id: token_id.to_string(),
+        sale_price,
+    };
+    if let Ok(mut res) = deps
+        .querier
+        .query_wasm_smart::<RoyaltiesInfoResponse>(collection, &query)
+    {
+        // Never pay out more than the sale price
+        res.royalty_amount = res.royalty_amount.min(sale_price);
+        return Ok(res);
+    }
+
+    if let Some(royalty) = COLLECTION_ROYALTIES.may_load(deps.storage, collection)? {
+        return Ok(RoyaltiesInfoResponse {
+            address: royalty.recipient.into_string(),
+            royalty_amount: sale_price.multiply_ratio(royalty.royalty, 100u128),
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    Ok(RoyaltiesInfoResponse {
+        address: state.collection_fabric_address.into_string(),
+        royalty_amount: sale_price.multiply_ratio(config.royalty, 100u128), // royalty = (price * royalty) / 100
+    })
+}
+
+fn transfer_nft_msg(collection: &str, recipient: &str, token_id: &str) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: collection.to_string(),
+        msg: to_json_binary(&nft::contract::Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn query_nft_owner(deps: Deps, collection: &str, token_id: &str) -> StdResult<Addr> {
+    let res: OwnerOfResponse = deps.querier.query_wasm_smart(
+        collection,
         &nft::contract::QueryMsg::OwnerOf {
-            token_id: token_id.clone(),
+            token_id: token_id.to_string(),
             include_expired: None,
         },
     )?;
+    Ok(Addr::unchecked(res.owner))
+}
+
+/// Resolves the payment asset requested for a listing, defaulting to the native denom.
+fn validate_denom(
+    deps: Deps,
+    config: &Config,
+    denom: Option<Denom>,
+) -> Result<Denom, ContractError> {
+    match denom {
+        None => Ok(Denom::Native(config.native_denom.clone())),
+        Some(Denom::Native(denom)) => {
+            if denom != config.native_denom {
+                return Err(ContractError::UnsupportedNativeDenom);
+            }
+            Ok(Denom::Native(denom))
+        }
+        Some(Denom::Cw20(token)) => {
+            let token = deps.api.addr_validate(token.as_str())?;
+            if !CW20_TOKENS.has(deps.storage, &token) {
+                return Err(ContractError::Cw20TokenNotAccepted);
+            }
+            Ok(Denom::Cw20(token))
+        }
+    }
+}
 
-    if nft_owner != owner {
-        return Err(StdError::generic_err("CallerIsNotNFTOwner"));
+fn denom_to_string(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.to_string(),
+        Denom::Cw20(token) => token.to_string(),
     }
+}
 
+#[allow(clippy::too_many_arguments)]
+pub fn execute_list_nft_for_auction(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    collection: String,
+    token_id: String,
+    start_price: Uint128,
+    min_bid_step: Uint128,
+    start_time: Uint64,
+    end_time: Uint64,
+    anti_sniping: Option<AntiSniping>,
+    reserve_price: Option<Uint128>,
+    hide_reserve_price: bool,
+    buy_now_price: Option<Uint128>,
+) -> Result<Response, ContractError> {
     // Validate auction parameters
     if start_price.is_zero() {
-        return Err(StdError::generic_err("AuctionPriceIsZero"));
+        return Err(ContractError::ZeroPrice);
     }
 
     if min_bid_step.is_zero() {
-        return Err(StdError::generic_err("AuctionMinBidStepIsZero"));
+        return Err(ContractError::ZeroMinBidStep);
     }
 
     if end_time < start_time {
-        return Err(StdError::generic_err("AuctionEndTimeIsBeforeStartTime"));
+        return Err(ContractError::AuctionEndTimeIsBeforeStartTime);
     }
 
     if start_time < Uint64::from(env.block.time.seconds()) {
-        return Err(StdError::generic_err("AuctionStartTimeIsBeforeNow"));
+        return Err(ContractError::AuctionStartTimeIsBeforeNow);
+    }
+
+    if reserve_price.is_some_and(|reserve_price| reserve_price < start_price) {
+        return Err(ContractError::InvalidReservePrice);
+    }
+
+    if buy_now_price.is_some_and(|buy_now_price| {
+        buy_now_price <= start_price || buy_now_price < reserve_price.unwrap_or_default()
+    }) {
+        return Err(ContractError::InvalidBuyNowPrice);
     }
 
+    // Auctions listed without an extension get the marketplace default
+    let anti_sniping = match anti_sniping {
+        Some(anti_sniping) => Some(anti_sniping),
+        None => CONFIG.load(deps.storage)?.anti_sniping,
+    };
+    let extension = anti_sniping
+        .map(|anti_sniping| -> Result<_, ContractError> {
+            validate_anti_sniping(&anti_sniping)?;
+            Ok(AuctionExtension {
+                extension_window: anti_sniping.extension_window,
+                extension_duration: anti_sniping.extension_duration,
+                max_end_time: anti_sniping
+                    .max_extension
+                    .map(|max_extension| end_time.checked_add(max_extension))
+                    .transpose()
+                    .map_err(StdError::from)?,
+            })
+        })
+        .transpose()?;
+
     // Create the auction
     let mut state = STATE.load(deps.storage)?;
     state.auction_count += 1;
@@ -455,17 +1200,22 @@ pub fn execute_list_nft_for_auction(
         seller: owner.clone(),
         collection: collection.clone(),
         token_id: token_id.clone(),
-        start_price: start_price.clone(),
-        min_bid_step: min_bid_step.clone(),
-        start_time: start_time.clone(),
-        end_time: end_time.clone(),
+        start_price,
+        min_bid_step,
+        start_time,
+        end_time,
         current_price: Uint128::zero(),
         current_bidder: None,
         status: AuctionStatus::WaitingAuction,
-        royalty: royalty.clone(),
+        kind: AuctionKind::English,
+        extension,
+        reserve_price,
+        reserve_hidden: reserve_price.is_some() && hide_reserve_price,
+        reserve_met: None,
+        buy_now_price,
     };
 
-    AUCTIONS.save(
+    auctions().save(
         deps.storage,
         (&collection.clone(), &token_id.clone()),
         &auction,
@@ -481,20 +1231,8 @@ pub fn execute_list_nft_for_auction(
         &deposit,
     )?;
 
-    // Transfer the NFT from the seller to the marketplace contract
-    let transfer_to_marketplace_msg = Cw721ExecuteMsg::TransferNft {
-        recipient: env.contract.address.to_string(), // Marketplace contract address
-        token_id: token_id.clone(),
-    };
-
-    let execute_transfer_to_marketplace = WasmMsg::Execute {
-        contract_addr: collection.to_string(),
-        msg: to_json_binary(&transfer_to_marketplace_msg)?,
-        funds: vec![],
-    };
     // Emit an event (using attributes in CosmWasm)
     let response = Response::new()
-        .add_message(execute_transfer_to_marketplace)
         .add_attribute("action", "list_nft_for_auction")
         .add_attribute("auction_id", state.auction_count.to_string())
         .add_attribute("creator", owner.clone())
@@ -508,33 +1246,110 @@ pub fn execute_list_nft_for_auction(
     Ok(response)
 }
 
+#[allow(clippy::too_many_arguments)]
+fn execute_list_nft_for_dutch_auction(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    collection: String,
+    token_id: String,
+    start_price: Uint128,
+    floor_price: Uint128,
+    start_time: Uint64,
+    end_time: Uint64,
+    curve: PriceCurve,
+) -> Result<Response, ContractError> {
+    ensure!(!floor_price.is_zero(), ContractError::ZeroPrice);
+    ensure!(floor_price < start_price, ContractError::InvalidFloorPrice);
+    ensure!(
+        end_time > start_time,
+        ContractError::AuctionEndTimeIsBeforeStartTime
+    );
+    ensure!(
+        start_time >= Uint64::from(env.block.time.seconds()),
+        ContractError::AuctionStartTimeIsBeforeNow
+    );
+    if let PriceCurve::Stepped { interval } = curve {
+        ensure!(!interval.is_zero(), ContractError::ZeroPriceStepInterval);
+    }
+
+    let mut state = STATE.load(deps.storage)?;
+    state.auction_count += 1;
+    STATE.save(deps.storage, &state)?;
+
+    // Dutch auctions are open to buyers from `start_time` on, without being started
+    let auction = Auction {
+        seller: owner.clone(),
+        collection: collection.clone(),
+        token_id: token_id.clone(),
+        start_price,
+        min_bid_step: Uint128::zero(),
+        start_time,
+        end_time,
+        current_price: Uint128::zero(),
+        current_bidder: None,
+        status: AuctionStatus::WaitingAuction,
+        kind: AuctionKind::Dutch {
+            floor_price,
+            curve: curve.clone(),
+        },
+        extension: None,
+        reserve_price: None,
+        reserve_hidden: false,
+        reserve_met: None,
+        buy_now_price: None,
+    };
+    auctions().save(deps.storage, (&collection, &token_id), &auction)?;
+    let deposit = Deposits {
+        owner: owner.clone(),
+        collection: collection.clone(),
+        token_id: token_id.clone(),
+    };
+    DEPOSITS.save(deps.storage, (&collection, &owner, &token_id), &deposit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "list_nft_for_dutch_auction")
+        .add_attribute("auction_id", state.auction_count.to_string())
+        .add_attribute("creator", owner)
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("start_price", start_price.to_string())
+        .add_attribute("floor_price", floor_price.to_string())
+        .add_attribute("start_time", start_time.to_string())
+        .add_attribute("end_time", end_time.to_string())
+        .add_attribute("curve", format!("{curve:?}")))
+}
+
 pub fn start_auction(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     collection: String,
     token_id: String, // account_manager_contract_addr: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Listing)?;
     let caller = info.sender;
     let current_time = Uint64::from(env.block.time.seconds());
 
-    let auction = AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
     match auction {
         Some(auction) => {
             if auction.seller != caller && !is_admin(deps.as_ref(), caller.clone())? {
-                return Err(StdError::generic_err(
-                    "Caller is not the auction owner or an admin",
-                ));
+                return Err(ContractError::Unauthorized);
             }
+            ensure!(
+                auction.kind == AuctionKind::English,
+                ContractError::NotEnglishAuction
+            );
 
             // Check if the auction is in the correct status
             if auction.status != AuctionStatus::WaitingAuction {
-                return Err(StdError::generic_err("AuctionNotWaiting"));
+                return Err(ContractError::AuctionNotWaiting);
             }
 
             // Check if the auction start time is valid
             if auction.start_time > current_time {
-                return Err(StdError::generic_err("AuctionStartTimeIsBeforeNow"));
+                return Err(ContractError::AuctionStartTimeIsBeforeNow);
             }
 
             // Update the auction status
@@ -543,7 +1358,7 @@ pub fn start_auction(
                 ..auction
             };
 
-            AUCTIONS.save(deps.storage, (&collection, &token_id), &updated_auction)?;
+            auctions().save(deps.storage, (&collection, &token_id), &updated_auction)?;
 
             // Emit an event (using attributes in CosmWasm)
             let response = Response::new()
@@ -554,31 +1369,32 @@ pub fn start_auction(
 
             Ok(response)
         }
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+        None => Err(ContractError::AuctionNotFound),
     }
 }
 
 pub fn cancel_auction(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     collection: String,
     token_id: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let caller = info.sender;
 
     // Load the auction
-    let auction = AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
     match auction {
         Some(auction) => {
             // Check if the caller is the auction creator or an admin
             if auction.seller != caller && !is_admin(deps.as_ref(), caller.clone())? {
-                return Err(StdError::generic_err("Caller is not the auction owner"));
+                return Err(ContractError::Unauthorized);
             }
 
-            // Check if the auction is in the correct status
-            if auction.status != AuctionStatus::WaitingAuction {
-                return Err(StdError::generic_err("Auction is not in waiting status"));
+            // Auctions can only be cancelled before they start
+            let status = auction.effective_status(Uint64::from(env.block.time.seconds()));
+            if status != AuctionStatus::WaitingAuction {
+                return Err(ContractError::AuctionNotWaiting);
             }
 
             // Update the auction status to Cancelled
@@ -587,7 +1403,7 @@ pub fn cancel_auction(
                 ..auction.clone()
             };
 
-            AUCTIONS.save(deps.storage, (&collection, &token_id), &updated_auction)?;
+            auctions().save(deps.storage, (&collection, &token_id), &updated_auction)?;
 
             DEPOSITS.remove(
                 deps.storage,
@@ -616,7 +1432,7 @@ pub fn cancel_auction(
 
             Ok(response)
         }
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+        None => Err(ContractError::AuctionNotFound),
     }
 }
 
@@ -627,76 +1443,165 @@ fn bid_nft(
     collection: String,
     token_id: String,
     price: Uint128,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Bidding)?;
     let config = CONFIG.load(deps.storage)?;
-    let auction = AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
     match auction {
         Some(auction) => {
             // Ensure auction is active
             ensure!(
-                auction.status == AuctionStatus::InAuction,
-                StdError::generic_err("Auction is not active")
-            );
-            ensure!(
-                Uint64::from(env.block.time.seconds()) < auction.end_time,
-                StdError::generic_err("Auction has ended")
+                auction.kind == AuctionKind::English,
+                ContractError::NotEnglishAuction
             );
+            ensure_auction_live(&auction, Uint64::from(env.block.time.seconds()))?;
 
             // Ensure bid is high enough
-            ensure!(
-                price >= auction.start_price,
-                StdError::generic_err("Bid price too low")
-            );
+            ensure!(price >= auction.start_price, ContractError::BidTooLow);
             let min_bid = auction
                 .current_price
                 .checked_add(auction.min_bid_step)
                 .unwrap_or(Uint128::MAX); // Handle overflow gracefully
             ensure!(
                 price >= min_bid || auction.current_bidder.is_none(),
-                StdError::generic_err("Bid price too low")
+                ContractError::BidTooLow
             );
 
-            // Transfer bid amount to contract
-            let transfer_funds_msg = BankMsg::Send {
-                to_address: env.contract.address.to_string(),
-                amount: vec![Coin {
-                    denom: config.native_denom.to_string(),
-                    amount: price,
-                }],
-            };
+            // The bid is paid in and escrowed in place of the previous one
+            let funds_sent = must_pay(&info, &config.native_denom)?;
+            ensure!(
+                funds_sent == price,
+                ContractError::InvalidFunds {
+                    expected: price,
+                    received: funds_sent
+                }
+            );
+            let refund = set_auction_escrow(deps.storage, &collection, &token_id, price)?;
 
-            let mut messages: Vec<BankMsg> = vec![transfer_funds_msg];
+            let mut messages: Vec<BankMsg> = vec![];
 
             // Refund previous bidder if exists
-            if let Some(prev_bidder) = auction.current_bidder.clone() {
+            if let Some(prev_bidder) = auction.current_bidder.clone().filter(|_| !refund.is_zero())
+            {
                 let refund_msg = BankMsg::Send {
                     to_address: prev_bidder.to_string(),
                     amount: vec![Coin {
                         denom: config.native_denom.to_string(),
-                        amount: auction.current_price,
+                        amount: refund,
                     }],
                 };
                 messages.push(refund_msg);
             }
 
+            // Bids close to the end push it out
+            let end_time = extended_end_time(&auction, Uint64::from(env.block.time.seconds()));
+            let mut events: Vec<Event> = vec![];
+            if end_time != auction.end_time {
+                events.push(
+                    Event::new("auction_extended")
+                        .add_attribute("collection", &collection)
+                        .add_attribute("token_id", &token_id)
+                        .add_attribute("end_time", end_time.to_string()),
+                );
+            }
+
             // Update auction state
             let updated_auction = Auction {
                 current_price: price,
                 current_bidder: Some(info.sender.clone()),
+                end_time,
                 ..auction.clone()
             };
-            AUCTIONS.save(deps.storage, (&collection, &token_id), &updated_auction)?;
+            auctions().save(deps.storage, (&collection, &token_id), &updated_auction)?;
 
             // Return response with messages
             Ok(Response::new()
                 .add_messages(messages)
+                .add_events(events)
                 .add_attribute("method", "bid_nft")
                 .add_attribute("collection", collection.clone())
                 .add_attribute("token_id", token_id.clone())
                 .add_attribute("bidder", info.sender.to_string())
                 .add_attribute("price", price.to_string()))
         }
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+        None => Err(ContractError::AuctionNotFound),
+    }
+}
+
+/// End time of the auction after a bid at `now`, pushed out when the bid falls inside the
+/// anti-sniping window.
+fn extended_end_time(auction: &Auction, now: Uint64) -> Uint64 {
+    let Some(extension) = &auction.extension else {
+        return auction.end_time;
+    };
+    if now.saturating_add(extension.extension_window) < auction.end_time {
+        return auction.end_time;
+    }
+    let mut end_time = auction
+        .end_time
+        .max(now.saturating_add(extension.extension_duration));
+    if let Some(max_end_time) = extension.max_end_time {
+        end_time = end_time.min(max_end_time).max(auction.end_time);
+    }
+    end_time
+}
+
+/// Records `amount` as the bid escrowed for the auction and returns the escrow it replaces.
+fn set_auction_escrow(
+    storage: &mut dyn Storage,
+    collection: &str,
+    token_id: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let previous = AUCTION_ESCROW
+        .may_load(storage, (collection, token_id))?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        AUCTION_ESCROW.remove(storage, (collection, token_id));
+    } else {
+        AUCTION_ESCROW.save(storage, (collection, token_id), &amount)?;
+    }
+    let total = TOTAL_AUCTION_ESCROW.may_load(storage)?.unwrap_or_default();
+    TOTAL_AUCTION_ESCROW.save(storage, &total.checked_sub(previous)?.checked_add(amount)?)?;
+    Ok(previous)
+}
+
+/// Records `amount` paid into an offer. Only native offers share the balance with bids.
+fn add_offer_escrow(storage: &mut dyn Storage, denom: &Denom, amount: Uint128) -> StdResult<()> {
+    if let Denom::Native(denom) = denom {
+        NATIVE_OFFER_ESCROW.update(storage, denom, |total| {
+            total
+                .unwrap_or_default()
+                .checked_add(amount)
+                .map_err(StdError::from)
+        })?;
+    }
+    Ok(())
+}
+
+/// Releases `amount` of an offer's escrow once it is paid out or refunded.
+fn release_offer_escrow(
+    storage: &mut dyn Storage,
+    denom: &Denom,
+    amount: Uint128,
+) -> StdResult<()> {
+    if let Denom::Native(denom) = denom {
+        NATIVE_OFFER_ESCROW.update(storage, denom, |total| {
+            total
+                .unwrap_or_default()
+                .checked_sub(amount)
+                .map_err(StdError::from)
+        })?;
+    }
+    Ok(())
+}
+
+/// Fails unless the auction is live at block time `now`.
+fn ensure_auction_live(auction: &Auction, now: Uint64) -> Result<(), ContractError> {
+    match auction.effective_status(now) {
+        AuctionStatus::InAuction => Ok(()),
+        AuctionStatus::WaitingForClaim => Err(ContractError::AuctionEnded),
+        _ => Err(ContractError::AuctionNotActive),
     }
 }
 
@@ -706,24 +1611,33 @@ fn claim_nft(
     info: MessageInfo,
     collection: String,
     token_id: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Claiming)?;
     let config = CONFIG.load(deps.storage)?;
-    let auction = AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
     match auction {
         Some(auction) => {
             // Ensure auction has ended
-            ensure!(
-                auction.status == AuctionStatus::InAuction,
-                StdError::generic_err("Auction is not active")
-            );
-            ensure!(
-                Uint64::from(env.block.time.seconds()) >= auction.end_time,
-                StdError::generic_err("Auction has not ended")
-            );
+            match auction.effective_status(Uint64::from(env.block.time.seconds())) {
+                AuctionStatus::WaitingForClaim => {}
+                AuctionStatus::Ended | AuctionStatus::Cancelled => {
+                    return Err(ContractError::AuctionNotActive)
+                }
+                _ => return Err(ContractError::AuctionNotEnded),
+            }
+
+            // The escrowed bid is paid out or refunded
+            let escrow = set_auction_escrow(deps.storage, &collection, &token_id, Uint128::zero())?;
+
+            // The NFT leaves the marketplace either way
+            DEPOSITS.remove(deps.storage, (&collection, &auction.seller, &token_id));
 
             let mut messages: Vec<CosmosMsg> = vec![];
+            let mut payout_attributes: Vec<Attribute> = vec![];
+            let reserve_met = auction.current_bidder.is_some()
+                && auction.current_price >= auction.reserve_price.unwrap_or_default();
 
-            if let Some(bidder) = auction.current_bidder.clone() {
+            if let Some(bidder) = auction.current_bidder.clone().filter(|_| reserve_met) {
                 // Transfer NFT to highest bidder
                 let transfer_nft_msg = CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: auction.collection.to_string(),
@@ -735,35 +1649,32 @@ fn claim_nft(
                 });
                 messages.push(transfer_nft_msg);
 
-                // Compute fee and seller's earnings
-                let fee = auction
-                    .current_price
-                    .multiply_ratio(auction.royalty, 100u128); // royalty = (listing.price * config.royalty) / 100
-                let without_fee = auction.current_price - fee;
-
-                let state = STATE.load(deps.storage)?;
-
-                // Transfer funds to seller
-                let transfer_to_creator_msg = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: auction.seller.to_string(),
-                    amount: vec![Coin {
-                        denom: config.native_denom.to_string(),
-                        amount: without_fee,
-                    }],
-                });
-                messages.push(transfer_to_creator_msg);
-
-                // Transfer royalty fee
-                let transfer_fee_msg = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: state.collection_fabric_address.to_string(),
-                    amount: vec![Coin {
-                        denom: config.native_denom.to_string(),
-                        amount: fee,
-                    }],
-                });
-                messages.push(transfer_fee_msg);
+                // Pay the seller, the marketplace fee and the royalty
+                let payout = sale_payout(
+                    deps.as_ref(),
+                    &Denom::Native(config.native_denom.clone()),
+                    &auction.collection,
+                    &auction.token_id,
+                    &auction.seller,
+                    auction.current_price,
+                )?;
+                payout_attributes = payout.attributes();
+                messages.extend(payout.messages);
             } else {
-                // No bids, return NFT to creator
+                // Refund the highest bid below the reserve price
+                if let Some(bidder) = auction
+                    .current_bidder
+                    .as_ref()
+                    .filter(|_| !escrow.is_zero())
+                {
+                    messages.push(payment_msg(
+                        &Denom::Native(config.native_denom.clone()),
+                        bidder.as_str(),
+                        escrow,
+                    )?);
+                }
+
+                // No winning bid, return NFT to creator
                 let transfer_nft_msg = CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: auction.collection.to_string(),
                     msg: to_json_binary(&nft::contract::Cw721ExecuteMsg::TransferNft {
@@ -778,107 +1689,3538 @@ fn claim_nft(
             // Update auction status
             let updated_auction = Auction {
                 status: AuctionStatus::Ended,
+                reserve_met: Some(reserve_met),
                 ..auction.clone()
             };
 
-            AUCTIONS.save(deps.storage, (&collection, &token_id), &updated_auction)?;
+            auctions().save(deps.storage, (&collection, &token_id), &updated_auction)?;
+
+            let event = Event::new("auction_settled")
+                .add_attribute("collection", &collection)
+                .add_attribute("token_id", &token_id)
+                .add_attribute("reserve_met", reserve_met.to_string())
+                .add_attribute("price", auction.current_price.to_string());
 
             // Return response with messages
             Ok(Response::new()
                 .add_messages(messages)
+                .add_event(event)
                 .add_attribute("method", "claim_nft")
                 .add_attribute("collection", collection.clone())
                 .add_attribute("token_id", token_id.clone())
-                .add_attribute("claimer", info.sender.to_string()))
+                .add_attribute("claimer", info.sender.to_string())
+                .add_attributes(payout_attributes))
         }
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+        None => Err(ContractError::AuctionNotFound),
     }
 }
 
-fn add_admin(deps: DepsMut, _env: Env, info: MessageInfo, account_id: Addr) -> StdResult<Response> {
-    let state = STATE.load(deps.storage)?;
-    if info.sender != state.owner {
-        return Err(StdError::generic_err("AdminAccessError"));
-    }
+fn buy_dutch_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    let auction = auctions()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::AuctionNotFound)?;
 
-    let mut admins = ADMINS.load(deps.storage).unwrap_or_default();
+    let now = Uint64::from(env.block.time.seconds());
+    let price = auction
+        .dutch_price(now)
+        .ok_or(ContractError::NotDutchAuction)?;
+    ensure_auction_live(&auction, now)?;
+    ensure!(
+        info.sender != auction.seller,
+        ContractError::CannotBuyOwnNft
+    );
+    let refund = funds_sent
+        .checked_sub(price)
+        .map_err(|_| ContractError::InvalidFunds {
+            expected: price,
+            received: funds_sent,
+        })?;
 
-    // Check if the account is already an admin
-    if admins.contains(&account_id) {
-        return Err(StdError::generic_err("AdminAccessError"));
+    // Sell the NFT and refund the overpayment
+    let denom = Denom::Native(config.native_denom);
+    let (mut messages, payout_attributes) =
+        settle_auction_sale(deps, &denom, &auction, &info.sender, price)?;
+    if !refund.is_zero() {
+        messages.push(payment_msg(&denom, info.sender.as_str(), refund)?);
     }
 
-    // Add the new admin
-    admins.push(account_id.clone());
-    ADMINS.save(deps.storage, &admins)?;
-
     Ok(Response::new()
-        .add_attribute("action", "add_admin")
-        .add_attribute("caller", info.sender.to_string())
-        .add_attribute("admin", account_id.to_string()))
+        .add_messages(messages)
+        .add_attribute("method", "buy_dutch_auction")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("buyer", info.sender.into_string())
+        .add_attribute("seller", auction.seller)
+        .add_attribute("price", price.to_string())
+        .add_attribute("refund", refund.to_string())
+        .add_attributes(payout_attributes))
 }
 
-fn remove_admin(
+fn buy_now(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    account_id: Addr,
-) -> StdResult<Response> {
-    let state = STATE.load(deps.storage)?;
-    if info.sender != state.owner {
-        return Err(StdError::generic_err("Admin access error"));
-    }
-
-    // Load the current list of admins
-    let mut admins = ADMINS.load(deps.storage).unwrap_or_default();
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    let auction = auctions()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::AuctionNotFound)?;
 
-    // Check if the account is an admin
-    if !admins.contains(&account_id) {
-        return Err(StdError::generic_err("AdminAccessError"));
+    let price = auction.buy_now_price.ok_or(ContractError::NoBuyNowPrice)?;
+    ensure!(
+        auction.current_bidder.is_none() || auction.current_price < price,
+        ContractError::BuyNowPriceReached
+    );
+    match auction.effective_status(Uint64::from(env.block.time.seconds())) {
+        AuctionStatus::WaitingAuction | AuctionStatus::InAuction => {}
+        AuctionStatus::WaitingForClaim => return Err(ContractError::AuctionEnded),
+        _ => return Err(ContractError::AuctionNotActive),
     }
+    ensure!(
+        info.sender != auction.seller,
+        ContractError::CannotBuyOwnNft
+    );
+    ensure!(
+        funds_sent == price,
+        ContractError::InvalidFunds {
+            expected: price,
+            received: funds_sent
+        }
+    );
 
-    // Remove the admin
-    admins.retain(|admin| admin != &account_id);
-    ADMINS.save(deps.storage, &admins)?;
+    // Refund the highest bid, then sell the NFT
+    let denom = Denom::Native(config.native_denom);
+    let escrow = set_auction_escrow(deps.storage, &collection, &token_id, Uint128::zero())?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(bidder) = auction
+        .current_bidder
+        .as_ref()
+        .filter(|_| !escrow.is_zero())
+    {
+        messages.push(payment_msg(&denom, bidder.as_str(), escrow)?);
+    }
+    let (sale_msgs, payout_attributes) =
+        settle_auction_sale(deps, &denom, &auction, &info.sender, price)?;
+    messages.extend(sale_msgs);
 
     Ok(Response::new()
-        .add_attribute("action", "remove_admin")
-        .add_attribute("caller", info.sender.to_string())
-        .add_attribute("admin", account_id.to_string()))
+        .add_messages(messages)
+        .add_attribute("method", "buy_now")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("buyer", info.sender.into_string())
+        .add_attribute("seller", auction.seller)
+        .add_attribute("price", price.to_string())
+        .add_attributes(payout_attributes))
 }
 
-pub fn is_admin(deps: Deps, account_id: Addr) -> StdResult<bool> {
-    let admins = ADMINS.load(deps.storage)?; // Use `?` to propagate errors
-    Ok(admins.contains(&account_id))
+/// Ends the auction with a sale to `buyer` at `price` and returns the messages transferring the
+/// NFT and paying the seller and the royalty.
+fn settle_auction_sale(
+    deps: DepsMut,
+    denom: &Denom,
+    auction: &Auction,
+    buyer: &Addr,
+    price: Uint128,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    let sold_auction = Auction {
+        current_price: price,
+        current_bidder: Some(buyer.clone()),
+        status: AuctionStatus::Ended,
+        reserve_met: auction.reserve_price.map(|_| true),
+        ..auction.clone()
+    };
+    auctions().save(
+        deps.storage,
+        (&auction.collection, &auction.token_id),
+        &sold_auction,
+    )?;
+    DEPOSITS.remove(
+        deps.storage,
+        (&auction.collection, &auction.seller, &auction.token_id),
+    );
+
+    let mut messages = vec![transfer_nft_msg(
+        &auction.collection,
+        buyer.as_str(),
+        &auction.token_id,
+    )?];
+    let payout = sale_payout(
+        deps.as_ref(),
+        denom,
+        &auction.collection,
+        &auction.token_id,
+        &auction.seller,
+        price,
+    )?;
+    let payout_attributes = payout.attributes();
+    messages.extend(payout.messages);
+    Ok((messages, payout_attributes))
 }
 
-// Implement the contract's query functions
-fn query_listing_count(deps: Deps) -> StdResult<u128> {
-    let state = STATE.load(deps.storage)?;
-    Ok(state.listing_count)
+fn make_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    price: Uint128,
+    expires: Uint64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    ensure!(
+        funds_sent == price,
+        ContractError::InvalidFunds {
+            expected: price,
+            received: funds_sent
+        }
+    );
+    execute_make_offer(
+        deps,
+        env,
+        info.sender,
+        collection,
+        token_id,
+        Denom::Native(config.native_denom),
+        price,
+        expires,
+    )
 }
 
-fn query_listing_by_index(deps: Deps, collection: String, token_id: String) -> StdResult<Listing> {
-    let listing = LISTINGS.may_load(deps.storage, (&collection, &token_id))?;
-    match listing {
-        Some(listing) => Ok(listing),
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
+#[allow(clippy::too_many_arguments)]
+fn execute_make_offer(
+    deps: DepsMut,
+    env: Env,
+    bidder: Addr,
+    collection: String,
+    token_id: String,
+    denom: Denom,
+    price: Uint128,
+    expires: Uint64,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Bidding)?;
+    let collection = deps.api.addr_validate(&collection)?.into_string();
+    ensure!(!price.is_zero(), ContractError::ZeroPrice);
+    ensure!(
+        expires > Uint64::from(env.block.time.seconds()),
+        ContractError::InvalidExpiration
+    );
+    if offers().has(deps.storage, (&collection, &token_id, &bidder)) {
+        return Err(ContractError::OfferAlreadyExists);
     }
+
+    let offer = Offer {
+        bidder: bidder.clone(),
+        collection: collection.clone(),
+        token_id: token_id.clone(),
+        price,
+        denom: denom.clone(),
+        expires,
+    };
+    offers().save(deps.storage, (&collection, &token_id, &bidder), &offer)?;
+    add_offer_escrow(deps.storage, &denom, price)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "make_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", bidder.to_string())
+        .add_attribute("price", price.to_string())
+        .add_attribute("denom", denom_to_string(&denom))
+        .add_attribute("expires", expires.to_string()))
 }
 
-fn query_auction_count(deps: Deps) -> StdResult<u128> {
-    let state = STATE.load(deps.storage)?;
-    Ok(state.auction_count)
+fn cancel_offer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let offer = offers()
+        .may_load(deps.storage, (&collection, &token_id, &info.sender))?
+        .ok_or(ContractError::OfferNotFound)?;
+    offers().remove(deps.storage, (&collection, &token_id, &offer.bidder))?;
+    release_offer_escrow(deps.storage, &offer.denom, offer.price)?;
+
+    // Refund the escrowed funds
+    let refund_msg = payment_msg(&offer.denom, offer.bidder.as_str(), offer.price)?;
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "cancel_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", offer.bidder.to_string()))
 }
 
-fn query_auction_by_index(deps: Deps, collection: String, token_id: String) -> StdResult<Auction> {
-    let auction = AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
-    match auction {
-        Some(auction) => Ok(auction),
-        None => Err(StdError::generic_err("TokenNotListedForSale")),
-    }
+fn retract_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    bidder: String,
+) -> Result<Response, ContractError> {
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let offer = offers()
+        .may_load(deps.storage, (&collection, &token_id, &bidder))?
+        .ok_or(ContractError::OfferNotFound)?;
+
+    // Anyone can clean up an expired offer, only the bidder can retract a live one
+    let expired = Uint64::from(env.block.time.seconds()) >= offer.expires;
+    ensure!(
+        expired || info.sender == offer.bidder,
+        ContractError::OfferNotExpired
+    );
+    offers().remove(deps.storage, (&collection, &token_id, &bidder))?;
+    release_offer_escrow(deps.storage, &offer.denom, offer.price)?;
+
+    // Refund the escrowed funds
+    let refund_msg = payment_msg(&offer.denom, offer.bidder.as_str(), offer.price)?;
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "retract_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", offer.bidder.to_string())
+        .add_attribute("caller", info.sender.to_string()))
 }
 
-fn query_is_admin(deps: Deps, account_id: Addr) -> StdResult<bool> {
-    is_admin(deps, account_id)
+fn accept_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    bidder: String,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let offer = offers()
+        .may_load(deps.storage, (&collection, &token_id, &bidder))?
+        .ok_or(ContractError::OfferNotFound)?;
+    ensure!(
+        Uint64::from(env.block.time.seconds()) < offer.expires,
+        ContractError::OfferExpired
+    );
+    ensure!(
+        info.sender != offer.bidder,
+        ContractError::CannotAcceptOwnOffer
+    );
+
+    prepare_offer_sale(deps.branch(), &info.sender, &collection, &token_id)?;
+    offers().remove(deps.storage, (&collection, &token_id, &bidder))?;
+    release_offer_escrow(deps.storage, &offer.denom, offer.price)?;
+
+    let mut messages = vec![transfer_nft_msg(
+        &collection,
+        offer.bidder.as_str(),
+        &token_id,
+    )?];
+    let payout = sale_payout(
+        deps.as_ref(),
+        &offer.denom,
+        &collection,
+        &token_id,
+        info.sender.as_str(),
+        offer.price,
+    )?;
+    let payout_attributes = payout.attributes();
+    messages.extend(payout.messages);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "accept_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", info.sender.to_string())
+        .add_attribute("bidder", offer.bidder.to_string())
+        .add_attribute("price", offer.price.to_string())
+        .add_attribute("denom", denom_to_string(&offer.denom))
+        .add_attributes(payout_attributes))
+}
+
+/// Clears the listing of an NFT sold into an offer, or checks that the seller owns it.
+fn prepare_offer_sale(
+    deps: DepsMut,
+    seller: &Addr,
+    collection: &str,
+    token_id: &str,
+) -> Result<(), ContractError> {
+    // A listed NFT is held by the marketplace and sold by its seller,
+    // otherwise the seller must own the NFT and have approved the marketplace
+    match listings().may_load(deps.storage, (collection, token_id))? {
+        Some(listing) => {
+            ensure!(*seller == listing.seller, ContractError::Unauthorized);
+            DEPOSITS.remove(deps.storage, (collection, &listing.seller, token_id));
+            listings().remove(deps.storage, (collection, token_id))?;
+            let mut state = STATE.load(deps.storage)?;
+            state.listing_count = state.listing_count.saturating_sub(1);
+            STATE.save(deps.storage, &state)?;
+        }
+        None => {
+            let nft_owner = query_nft_owner(deps.as_ref(), collection, token_id)?;
+            ensure!(*seller == nft_owner, ContractError::NotNftOwner);
+        }
+    }
+    Ok(())
+}
+
+fn make_collection_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    price: Uint128,
+    quantity: u32,
+    expires: Uint64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    execute_make_collection_offer(
+        deps,
+        env,
+        info.sender,
+        collection,
+        Denom::Native(config.native_denom),
+        price,
+        quantity,
+        funds_sent,
+        expires,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_make_collection_offer(
+    deps: DepsMut,
+    env: Env,
+    bidder: Addr,
+    collection: String,
+    denom: Denom,
+    price: Uint128,
+    quantity: u32,
+    funds_sent: Uint128,
+    expires: Uint64,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Bidding)?;
+    let collection = deps.api.addr_validate(&collection)?.into_string();
+    ensure!(!price.is_zero(), ContractError::ZeroPrice);
+    ensure!(quantity > 0, ContractError::ZeroQuantity);
+    let total = price
+        .checked_mul(Uint128::from(quantity))
+        .map_err(StdError::from)?;
+    ensure!(
+        funds_sent == total,
+        ContractError::InvalidFunds {
+            expected: total,
+            received: funds_sent
+        }
+    );
+    ensure!(
+        expires > Uint64::from(env.block.time.seconds()),
+        ContractError::InvalidExpiration
+    );
+    if collection_offers().has(deps.storage, (&collection, &bidder)) {
+        return Err(ContractError::OfferAlreadyExists);
+    }
+
+    let offer = CollectionOffer {
+        bidder: bidder.clone(),
+        collection: collection.clone(),
+        price,
+        denom: denom.clone(),
+        quantity,
+        expires,
+    };
+    collection_offers().save(deps.storage, (&collection, &bidder), &offer)?;
+    add_offer_escrow(deps.storage, &denom, total)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "make_collection_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("bidder", bidder.to_string())
+        .add_attribute("price", price.to_string())
+        .add_attribute("quantity", quantity.to_string())
+        .add_attribute("denom", denom_to_string(&denom))
+        .add_attribute("expires", expires.to_string()))
+}
+
+fn cancel_collection_offer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let offer = collection_offers()
+        .may_load(deps.storage, (&collection, &info.sender))?
+        .ok_or(ContractError::OfferNotFound)?;
+    collection_offers().remove(deps.storage, (&collection, &offer.bidder))?;
+
+    // Refund the unfilled part of the offer
+    let refund = offer.price * Uint128::from(offer.quantity);
+    release_offer_escrow(deps.storage, &offer.denom, refund)?;
+    let refund_msg = payment_msg(&offer.denom, offer.bidder.as_str(), refund)?;
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "cancel_collection_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("bidder", offer.bidder.to_string())
+        .add_attribute("refund", refund.to_string()))
+}
+
+fn accept_collection_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    bidder: String,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let mut offer = collection_offers()
+        .may_load(deps.storage, (&collection, &bidder))?
+        .ok_or(ContractError::OfferNotFound)?;
+    ensure!(
+        Uint64::from(env.block.time.seconds()) < offer.expires,
+        ContractError::OfferExpired
+    );
+    ensure!(
+        info.sender != offer.bidder,
+        ContractError::CannotAcceptOwnOffer
+    );
+
+    prepare_offer_sale(deps.branch(), &info.sender, &collection, &token_id)?;
+
+    // Fill one token, the offer is removed once its quantity runs out
+    offer.quantity -= 1;
+    if offer.quantity == 0 {
+        collection_offers().remove(deps.storage, (&collection, &bidder))?;
+    } else {
+        collection_offers().save(deps.storage, (&collection, &bidder), &offer)?;
+    }
+    release_offer_escrow(deps.storage, &offer.denom, offer.price)?;
+
+    let mut messages = vec![transfer_nft_msg(
+        &collection,
+        offer.bidder.as_str(),
+        &token_id,
+    )?];
+    let payout = sale_payout(
+        deps.as_ref(),
+        &offer.denom,
+        &collection,
+        &token_id,
+        info.sender.as_str(),
+        offer.price,
+    )?;
+    let payout_attributes = payout.attributes();
+    messages.extend(payout.messages);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "accept_collection_offer")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", info.sender.to_string())
+        .add_attribute("bidder", offer.bidder.to_string())
+        .add_attribute("price", offer.price.to_string())
+        .add_attribute("remaining_quantity", offer.quantity.to_string())
+        .add_attribute("denom", denom_to_string(&offer.denom))
+        .add_attributes(payout_attributes))
+}
+
+fn list_bundle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    items: Vec<(String, String)>,
+    price: Uint128,
+    denom: Option<Denom>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Listing)?;
+    let seller = info.sender.into_string();
+    ensure!(
+        items.len() > 1 && items.len() <= MAX_BUNDLE_ITEMS,
+        ContractError::InvalidBundleSize {
+            max: MAX_BUNDLE_ITEMS
+        }
+    );
+    ensure!(!price.is_zero(), ContractError::ZeroPrice);
+
+    let config = CONFIG.load(deps.storage)?;
+    let denom = validate_denom(deps.as_ref(), &config, denom)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (index, (collection, token_id)) in items.iter().enumerate() {
+        ensure!(
+            !items[..index].contains(&(collection.clone(), token_id.clone())),
+            ContractError::DuplicateBundleItem
+        );
+        ensure_collection_allowed(deps.as_ref(), collection)?;
+        if DEPOSITS.has(deps.storage, (collection, &seller, token_id)) {
+            return Err(ContractError::AlreadyListed);
+        }
+        let nft_owner = query_nft_owner(deps.as_ref(), collection, token_id)?;
+        ensure!(nft_owner == seller, ContractError::NotNftOwner);
+
+        let deposit = Deposits {
+            owner: seller.clone(),
+            collection: collection.clone(),
+            token_id: token_id.clone(),
+        };
+        DEPOSITS.save(deps.storage, (collection, &seller, token_id), &deposit)?;
+
+        // Escrow every NFT of the bundle in the marketplace
+        messages.push(transfer_nft_msg(
+            collection,
+            env.contract.address.as_str(),
+            token_id,
+        )?);
+    }
+
+    let id = BUNDLE_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    BUNDLE_SEQ.save(deps.storage, &id)?;
+    let bundle = Bundle {
+        id,
+        seller: seller.clone(),
+        items,
+        price,
+        denom: denom.clone(),
+    };
+    BUNDLES.save(deps.storage, id, &bundle)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "list_bundle")
+        .add_attribute("bundle_id", id.to_string())
+        .add_attribute("seller", seller)
+        .add_attribute("price", price.to_string())
+        .add_attribute("denom", denom_to_string(&denom)))
+}
+
+fn cancel_bundle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    bundle_id: u64,
+) -> Result<Response, ContractError> {
+    let bundle = BUNDLES
+        .may_load(deps.storage, bundle_id)?
+        .ok_or(ContractError::BundleNotFound)?;
+    ensure!(info.sender == bundle.seller, ContractError::Unauthorized);
+
+    // Return every NFT of the bundle to the seller
+    let messages = release_bundle(deps, &bundle, &bundle.seller)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "cancel_bundle")
+        .add_attribute("bundle_id", bundle_id.to_string()))
+}
+
+fn buy_bundle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    bundle_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funds_sent = must_pay(&info, &config.native_denom)?;
+    execute_buy_bundle(
+        deps,
+        env,
+        info.sender,
+        bundle_id,
+        Denom::Native(config.native_denom),
+        funds_sent,
+    )
+}
+
+fn execute_buy_bundle(
+    deps: DepsMut,
+    _env: Env,
+    buyer: Addr,
+    bundle_id: u64,
+    denom: Denom,
+    funds_sent: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, TradingOperation::Buying)?;
+    let bundle = BUNDLES
+        .may_load(deps.storage, bundle_id)?
+        .ok_or(ContractError::BundleNotFound)?;
+    ensure!(buyer != bundle.seller, ContractError::CannotBuyOwnNft);
+    ensure!(denom == bundle.denom, ContractError::InvalidDenom);
+    ensure!(
+        funds_sent == bundle.price,
+        ContractError::InvalidFunds {
+            expected: bundle.price,
+            received: funds_sent
+        }
+    );
+
+    let config = CONFIG.load(deps.storage)?;
+    let protocol_fee = protocol_fee(&config, bundle.price);
+    let after_fee = bundle
+        .price
+        .checked_sub(protocol_fee)
+        .map_err(StdError::from)?;
+
+    // Each token carries an equal share of the price, its royalty is taken from that share
+    // and the royalties together never exceed what is left after the fee
+    let share = bundle
+        .price
+        .multiply_ratio(1u128, bundle.items.len() as u128);
+    let mut royalty_total = Uint128::zero();
+    let mut royalty_msgs: Vec<CosmosMsg> = vec![];
+    let mut royalty_events: Vec<Event> = vec![];
+    for (collection, token_id) in &bundle.items {
+        let mut royalty = query_royalty(deps.as_ref(), collection, token_id, share)?;
+        royalty.royalty_amount = royalty.royalty_amount.min(after_fee - royalty_total);
+        if !royalty.royalty_amount.is_zero() {
+            royalty_msgs.push(payment_msg(
+                &bundle.denom,
+                &royalty.address,
+                royalty.royalty_amount,
+            )?);
+            royalty_events.push(
+                Event::new("bundle_royalty")
+                    .add_attribute("collection", collection)
+                    .add_attribute("token_id", token_id)
+                    .add_attribute("recipient", royalty.address)
+                    .add_attribute("amount", royalty.royalty_amount.to_string()),
+            );
+            royalty_total += royalty.royalty_amount;
+        }
+    }
+
+    let earnings = after_fee - royalty_total;
+
+    let mut messages = release_bundle(deps, &bundle, buyer.as_str())?;
+    if !earnings.is_zero() {
+        messages.push(payment_msg(&bundle.denom, &bundle.seller, earnings)?);
+    }
+    if !protocol_fee.is_zero() {
+        messages.push(payment_msg(
+            &bundle.denom,
+            config.fee_collector.as_str(),
+            protocol_fee,
+        )?);
+    }
+    messages.extend(royalty_msgs);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(royalty_events)
+        .add_attribute("action", "buy_bundle")
+        .add_attribute("bundle_id", bundle_id.to_string())
+        .add_attribute("buyer", buyer.into_string())
+        .add_attribute("seller", bundle.seller)
+        .add_attribute("price", bundle.price.to_string())
+        .add_attribute("protocol_fee", protocol_fee.to_string())
+        .add_attribute("royalty", royalty_total.to_string())
+        .add_attribute("denom", denom_to_string(&bundle.denom)))
+}
+
+/// Removes the bundle and transfers all of its NFTs to `recipient`.
+fn release_bundle(deps: DepsMut, bundle: &Bundle, recipient: &str) -> StdResult<Vec<CosmosMsg>> {
+    BUNDLES.remove(deps.storage, bundle.id);
+    bundle
+        .items
+        .iter()
+        .map(|(collection, token_id)| {
+            DEPOSITS.remove(deps.storage, (collection, &bundle.seller, token_id));
+            transfer_nft_msg(collection, recipient, token_id)
+        })
+        .collect()
+}
+
+fn ensure_not_paused(
+    storage: &dyn Storage,
+    operation: TradingOperation,
+) -> Result<(), ContractError> {
+    let pause_state = PAUSE_STATE.may_load(storage)?.unwrap_or_default();
+    ensure!(
+        !pause_state.is_paused(operation),
+        ContractError::Paused { operation }
+    );
+    Ok(())
+}
+
+fn set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operations: Vec<TradingOperation>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    ensure_owner_or_admin(deps.as_ref(), &info.sender)?;
+
+    let mut pause_state = PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default();
+    for operation in &operations {
+        pause_state.set(*operation, paused);
+    }
+    PAUSE_STATE.save(deps.storage, &pause_state)?;
+
+    let operations = operations
+        .iter()
+        .map(|operation| format!("{operation:?}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(Response::new()
+        .add_attribute("action", if paused { "pause" } else { "unpause" })
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("operations", operations))
+}
+
+/// Fails unless `sender` is the owner, or with `NoOwner` once ownership has been renounced.
+fn ensure_owner(state: &State, sender: &Addr) -> Result<(), ContractError> {
+    match &state.owner {
+        Some(owner) if owner == sender => Ok(()),
+        Some(_) => Err(ContractError::Unauthorized),
+        None => Err(ContractError::NoOwner),
+    }
+}
+
+fn ensure_owner_or_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if ensure_owner(&state, sender).is_err() && !is_admin(deps, sender.clone())? {
+        return Err(ContractError::Unauthorized);
+    }
+    Ok(())
+}
+
+fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    if let Some(expiry) = expiry {
+        ensure!(
+            !expiry.is_expired(&env.block),
+            ContractError::OwnershipProposalExpired
+        );
+    }
+
+    let owner = deps.api.addr_validate(&owner)?;
+    OWNERSHIP_PROPOSAL.save(
+        deps.storage,
+        &OwnershipProposal {
+            owner: owner.clone(),
+            expiry,
+        },
+    )?;
+
+    let expiry = expiry.map(|expiry| expiry.to_string()).unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("pending_owner", owner.to_string())
+        .add_attribute("pending_expiry", expiry))
+}
+
+fn accept_ownership(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let proposal = OWNERSHIP_PROPOSAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOwnershipProposal)?;
+    ensure!(info.sender == proposal.owner, ContractError::Unauthorized);
+    if let Some(expiry) = proposal.expiry {
+        ensure!(
+            !expiry.is_expired(&env.block),
+            ContractError::OwnershipProposalExpired
+        );
+    }
+
+    let mut state = STATE.load(deps.storage)?;
+    let previous_owner = state.owner.replace(proposal.owner);
+    STATE.save(deps.storage, &state)?;
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    let previous_owner = previous_owner.map(Addr::into_string).unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", info.sender.to_string()))
+}
+
+fn cancel_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    ensure!(
+        OWNERSHIP_PROPOSAL.may_load(deps.storage)?.is_some(),
+        ContractError::NoOwnershipProposal
+    );
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ownership_proposal")
+        .add_attribute("owner", info.sender.to_string()))
+}
+
+fn renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    state.owner = None;
+    STATE.save(deps.storage, &state)?;
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("previous_owner", info.sender.to_string()))
+}
+
+fn add_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    account_id: Addr,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+
+    // Check if the account is already an admin
+    if ADMINS.has(deps.storage, &account_id) {
+        return Err(ContractError::AdminExists);
+    }
+
+    // Add the new admin
+    ADMINS.save(deps.storage, &account_id, &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_admin")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("admin", account_id.to_string()))
+}
+
+fn remove_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    account_id: Addr,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+
+    // Check if the account is an admin
+    if !ADMINS.has(deps.storage, &account_id) {
+        return Err(ContractError::AdminNotFound);
+    }
+
+    // Remove the admin
+    ADMINS.remove(deps.storage, &account_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_admin")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("admin", account_id.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    native_denom: Option<String>,
+    royalty: Option<u128>,
+    protocol_fee_bps: Option<u64>,
+    fee_collector: Option<String>,
+    collection_fabric_address: Option<String>,
+    strict_collections: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Record the old and new value of every field that is set
+    let mut changes: Vec<Attribute> = vec![];
+
+    if let Some(native_denom) = native_denom {
+        validate_native_denom(&native_denom)?;
+        // Auctions don't record their denom, escrowed bids must be paid out in the one they came in
+        ensure!(
+            TOTAL_AUCTION_ESCROW
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                .is_zero(),
+            ContractError::AuctionEscrowOutstanding
+        );
+        changes.extend(config_change(
+            "native_denom",
+            config.native_denom.clone(),
+            native_denom.clone(),
+        ));
+        config.native_denom = native_denom;
+    }
+    if let Some(royalty) = royalty {
+        validate_royalty(royalty)?;
+        changes.extend(config_change(
+            "royalty",
+            config.royalty.to_string(),
+            royalty.to_string(),
+        ));
+        config.royalty = royalty;
+    }
+    if let Some(protocol_fee_bps) = protocol_fee_bps {
+        validate_protocol_fee(protocol_fee_bps)?;
+        changes.extend(config_change(
+            "protocol_fee_bps",
+            config.protocol_fee_bps.to_string(),
+            protocol_fee_bps.to_string(),
+        ));
+        config.protocol_fee_bps = protocol_fee_bps;
+    }
+    if let Some(fee_collector) = fee_collector {
+        let fee_collector = deps.api.addr_validate(&fee_collector)?;
+        changes.extend(config_change(
+            "fee_collector",
+            config.fee_collector.to_string(),
+            fee_collector.to_string(),
+        ));
+        config.fee_collector = fee_collector;
+    }
+    if let Some(collection_fabric_address) = collection_fabric_address {
+        let collection_fabric_address = deps.api.addr_validate(&collection_fabric_address)?;
+        changes.extend(config_change(
+            "collection_fabric_address",
+            state.collection_fabric_address.to_string(),
+            collection_fabric_address.to_string(),
+        ));
+        state.collection_fabric_address = collection_fabric_address;
+    }
+    if let Some(strict_collections) = strict_collections {
+        changes.extend(config_change(
+            "strict_collections",
+            config.strict_collections.to_string(),
+            strict_collections.to_string(),
+        ));
+        config.strict_collections = strict_collections;
+    }
+    CONFIG.save(deps.storage, &config)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("config_updated")
+                .add_attribute("caller", info.sender.to_string())
+                .add_attributes(changes),
+        )
+        .add_attribute("action", "update_config"))
+}
+
+fn config_change(field: &str, old: String, new: String) -> [Attribute; 2] {
+    [
+        attr(format!("old_{field}"), old),
+        attr(format!("new_{field}"), new),
+    ]
+}
+
+/// Cosmos SDK denom format: 3 to 128 characters, starting with a letter.
+fn validate_native_denom(denom: &str) -> Result<(), ContractError> {
+    let valid = (3..=128).contains(&denom.len())
+        && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-'));
+    ensure!(
+        valid,
+        ContractError::InvalidNativeDenom {
+            denom: denom.to_string()
+        }
+    );
+    Ok(())
+}
+
+fn validate_royalty(royalty: u128) -> Result<(), ContractError> {
+    ensure!(royalty <= 100, ContractError::InvalidRoyalty);
+    Ok(())
+}
+
+fn validate_protocol_fee(protocol_fee_bps: u64) -> Result<(), ContractError> {
+    ensure!(
+        protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+        ContractError::InvalidProtocolFee {
+            max: MAX_PROTOCOL_FEE_BPS
+        }
+    );
+    Ok(())
+}
+
+fn set_default_anti_sniping(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    anti_sniping: Option<AntiSniping>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    if let Some(anti_sniping) = &anti_sniping {
+        validate_anti_sniping(anti_sniping)?;
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.anti_sniping = anti_sniping.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "set_default_anti_sniping")
+        .add_attribute("caller", info.sender.to_string());
+    if let Some(anti_sniping) = anti_sniping {
+        response = response
+            .add_attribute(
+                "extension_window",
+                anti_sniping.extension_window.to_string(),
+            )
+            .add_attribute(
+                "extension_duration",
+                anti_sniping.extension_duration.to_string(),
+            );
+    }
+    Ok(response)
+}
+
+fn validate_anti_sniping(anti_sniping: &AntiSniping) -> Result<(), ContractError> {
+    ensure!(
+        !anti_sniping.extension_window.is_zero() && !anti_sniping.extension_duration.is_zero(),
+        ContractError::InvalidAntiSniping
+    );
+    let max = Uint64::new(MAX_ANTI_SNIPING_SECONDS);
+    ensure!(
+        anti_sniping.extension_window <= max
+            && anti_sniping.extension_duration <= max
+            && anti_sniping.max_extension.unwrap_or_default() <= max,
+        ContractError::InvalidAntiSniping
+    );
+    Ok(())
+}
+
+fn add_cw20_token(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+
+    let token = deps.api.addr_validate(&token)?;
+    if CW20_TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::Cw20TokenAlreadyAccepted);
+    }
+    CW20_TOKENS.save(deps.storage, &token, &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_cw20_token")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("token", token.to_string()))
+}
+
+fn remove_cw20_token(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+
+    let token = deps.api.addr_validate(&token)?;
+    if !CW20_TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::Cw20TokenNotAccepted);
+    }
+    CW20_TOKENS.remove(deps.storage, &token);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_cw20_token")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("token", token.to_string()))
+}
+
+fn set_collection_royalty(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+    recipient: String,
+    royalty: Uint128,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    ensure!(royalty <= Uint128::new(100), ContractError::InvalidRoyalty);
+
+    let collection = deps.api.addr_validate(&collection)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    COLLECTION_ROYALTIES.save(
+        deps.storage,
+        collection.as_str(),
+        &CollectionRoyalty {
+            recipient: recipient.clone(),
+            royalty,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_collection_royalty")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("collection", collection.to_string())
+        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("royalty", royalty.to_string()))
+}
+
+fn register_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+    name: String,
+    creator: String,
+    verified: bool,
+) -> Result<Response, ContractError> {
+    ensure_owner_or_admin(deps.as_ref(), &info.sender)?;
+
+    let collection = deps.api.addr_validate(&collection)?;
+    let creator = deps.api.addr_validate(&creator)?;
+    COLLECTIONS.save(
+        deps.storage,
+        &collection,
+        &CollectionInfo {
+            collection: collection.clone(),
+            name: name.clone(),
+            creator: creator.clone(),
+            verified,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_collection")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("collection", collection.to_string())
+        .add_attribute("name", name)
+        .add_attribute("creator", creator.to_string())
+        .add_attribute("verified", verified.to_string()))
+}
+
+fn unregister_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    ensure_owner_or_admin(deps.as_ref(), &info.sender)?;
+
+    let collection = deps.api.addr_validate(&collection)?;
+    ensure!(
+        COLLECTIONS.has(deps.storage, &collection),
+        ContractError::CollectionNotRegistered
+    );
+    COLLECTIONS.remove(deps.storage, &collection);
+
+    Ok(Response::new()
+        .add_attribute("action", "unregister_collection")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("collection", collection.to_string()))
+}
+
+/// A token with a listing or an open auction can't be listed again, the new terms would
+/// overwrite it along with its bids
+fn ensure_not_listed(deps: Deps, collection: &str, token_id: &str) -> Result<(), ContractError> {
+    let auction_open = auctions()
+        .may_load(deps.storage, (collection, token_id))?
+        .is_some_and(|auction| {
+            !matches!(
+                auction.status,
+                AuctionStatus::Ended | AuctionStatus::Cancelled
+            )
+        });
+    ensure!(
+        !listings().has(deps.storage, (collection, token_id)) && !auction_open,
+        ContractError::AlreadyListed
+    );
+    Ok(())
+}
+
+/// In strict mode only registered collections can be listed
+fn ensure_collection_allowed(deps: Deps, collection: &str) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.strict_collections {
+        let collection = deps.api.addr_validate(collection)?;
+        ensure!(
+            COLLECTIONS.has(deps.storage, &collection),
+            ContractError::CollectionNotRegistered
+        );
+    }
+    Ok(())
+}
+
+fn remove_collection_royalty(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+
+    COLLECTION_ROYALTIES.remove(deps.storage, &collection);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_collection_royalty")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("collection", collection))
+}
+
+pub fn is_admin(deps: Deps, account_id: Addr) -> StdResult<bool> {
+    Ok(ADMINS.has(deps.storage, &account_id))
+}
+
+// Implement the contract's query functions
+fn query_listing_count(deps: Deps) -> StdResult<u128> {
+    let state = STATE.load(deps.storage)?;
+    Ok(state.listing_count)
+}
+
+fn query_listing_by_index(deps: Deps, collection: String, token_id: String) -> StdResult<Listing> {
+    let listing = listings().may_load(deps.storage, (&collection, &token_id))?;
+    match listing {
+        Some(listing) => Ok(listing),
+        None => Err(StdError::not_found("Listing")),
+    }
+}
+
+fn query_auction_count(deps: Deps) -> StdResult<u128> {
+    let state = STATE.load(deps.storage)?;
+    Ok(state.auction_count)
+}
+
+fn query_escrow_invariant(deps: Deps, env: Env) -> StdResult<EscrowInvariantResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let auction_escrow = TOTAL_AUCTION_ESCROW
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let offer_escrow = NATIVE_OFFER_ESCROW
+        .may_load(deps.storage, &config.native_denom)?
+        .unwrap_or_default();
+    let total_escrow = auction_escrow.checked_add(offer_escrow)?;
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address, &config.native_denom)?
+        .amount;
+    Ok(EscrowInvariantResponse {
+        denom: config.native_denom,
+        auction_escrow,
+        offer_escrow,
+        total_escrow,
+        balance,
+        holds: balance >= total_escrow,
+    })
+}
+
+fn query_current_dutch_price(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> StdResult<Uint128> {
+    let auction = query_auction_by_index(deps, env.clone(), collection, token_id)?;
+    auction
+        .dutch_price(Uint64::from(env.block.time.seconds()))
+        .ok_or_else(|| StdError::generic_err(ContractError::NotDutchAuction.to_string()))
+}
+
+fn query_auction_by_index(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> StdResult<Auction> {
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
+    match auction {
+        Some(auction) => Ok(auction.public(Uint64::from(env.block.time.seconds()))),
+        None => Err(StdError::not_found("Auction")),
+    }
+}
+
+fn query_list_listings(
+    deps: Deps,
+    collection: Option<String>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Listing>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    match collection {
+        Some(collection) => {
+            let start = start_after
+                .as_ref()
+                .map(|(_, token_id)| Bound::exclusive(token_id.as_str()));
+            listings()
+                .prefix(&collection)
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, listing)| listing))
+                .collect()
+        }
+        None => {
+            let start = start_after.as_ref().map(|(collection, token_id)| {
+                Bound::exclusive((collection.as_str(), token_id.as_str()))
+            });
+            listings()
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, listing)| listing))
+                .collect()
+        }
+    }
+}
+
+fn query_list_auctions(
+    deps: Deps,
+    env: Env,
+    collection: Option<String>,
+    status: Option<AuctionStatus>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Auction>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let now = Uint64::from(env.block.time.seconds());
+    let matches_status = |auction: &StdResult<Auction>| match (auction, &status) {
+        (Ok(auction), Some(status)) => auction.status == *status,
+        _ => true,
+    };
+
+    match collection {
+        Some(collection) => {
+            let start = start_after
+                .as_ref()
+                .map(|(_, token_id)| Bound::exclusive(token_id.as_str()));
+            auctions()
+                .prefix(&collection)
+                .range(deps.storage, start, None, Order::Ascending)
+                .map(|item| item.map(|(_, auction)| auction.public(now)))
+                .filter(matches_status)
+                .take(limit)
+                .collect()
+        }
+        None => {
+            let start = start_after.as_ref().map(|(collection, token_id)| {
+                Bound::exclusive((collection.as_str(), token_id.as_str()))
+            });
+            auctions()
+                .range(deps.storage, start, None, Order::Ascending)
+                .map(|item| item.map(|(_, auction)| auction.public(now)))
+                .filter(matches_status)
+                .take(limit)
+                .collect()
+        }
+    }
+}
+
+fn query_listings_by_seller(
+    deps: Deps,
+    seller: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Listing>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    listings()
+        .idx
+        .seller
+        .prefix(seller)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, listing)| listing))
+        .collect()
+}
+
+fn query_private_listings(
+    deps: Deps,
+    buyer: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Listing>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let buyer = deps.api.addr_validate(&buyer)?;
+    let start = start_after.map(Bound::exclusive);
+
+    listings()
+        .idx
+        .reserved_for
+        .prefix(buyer.into_string())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, listing)| listing))
+        .collect()
+}
+
+fn query_bundle(deps: Deps, bundle_id: u64) -> StdResult<Bundle> {
+    BUNDLES
+        .may_load(deps.storage, bundle_id)?
+        .ok_or_else(|| StdError::not_found("Bundle"))
+}
+
+fn query_list_bundles(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Bundle>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    BUNDLES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bundle)| bundle))
+        .collect()
+}
+
+fn query_auctions_by_seller(
+    deps: Deps,
+    env: Env,
+    seller: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Auction>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let now = Uint64::from(env.block.time.seconds());
+    let start = start_after.map(Bound::exclusive);
+
+    auctions()
+        .idx
+        .seller
+        .prefix(seller)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction.public(now)))
+        .collect()
+}
+
+fn query_auctions_by_bidder(
+    deps: Deps,
+    env: Env,
+    bidder: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Auction>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let now = Uint64::from(env.block.time.seconds());
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let start = start_after.map(Bound::exclusive);
+
+    auctions()
+        .idx
+        .bidder
+        .prefix(bidder.into_string())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction.public(now)))
+        .collect()
+}
+
+fn query_is_admin(deps: Deps, account_id: Addr) -> StdResult<bool> {
+    is_admin(deps, account_id)
+}
+
+fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let state = STATE.load(deps.storage)?;
+    let proposal = OWNERSHIP_PROPOSAL.may_load(deps.storage)?;
+    Ok(OwnershipResponse {
+        owner: state.owner,
+        pending_owner: proposal.as_ref().map(|proposal| proposal.owner.clone()),
+        pending_expiry: proposal.and_then(|proposal| proposal.expiry),
+    })
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: state.owner,
+        native_denom: config.native_denom,
+        royalty: config.royalty,
+        protocol_fee_bps: config.protocol_fee_bps,
+        fee_collector: config.fee_collector,
+        collection_fabric_address: state.collection_fabric_address,
+        strict_collections: config.strict_collections,
+        anti_sniping: config.anti_sniping,
+    })
+}
+
+fn query_collection(deps: Deps, collection: String) -> StdResult<CollectionInfo> {
+    let collection = deps.api.addr_validate(&collection)?;
+    COLLECTIONS
+        .may_load(deps.storage, &collection)?
+        .ok_or_else(|| StdError::not_found("Collection"))
+}
+
+fn query_list_collections(
+    deps: Deps,
+    verified_only: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CollectionInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|collection| deps.api.addr_validate(&collection))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    COLLECTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .map(|item| item.map(|(_, info)| info))
+        .filter(|item| !verified_only || item.as_ref().map_or(true, |info| info.verified))
+        .take(limit)
+        .collect()
+}
+
+fn query_fee_schedule(deps: Deps) -> StdResult<FeeScheduleResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    Ok(FeeScheduleResponse {
+        protocol_fee_bps: config.protocol_fee_bps,
+        fee_collector: config.fee_collector,
+        default_royalty: config.royalty,
+        default_royalty_recipient: state.collection_fabric_address,
+    })
+}
+
+fn query_cw20_tokens(deps: Deps) -> StdResult<Vec<Addr>> {
+    CW20_TOKENS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn query_offers_by_token(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Offer>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|bidder| deps.api.addr_validate(&bidder))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    offers()
+        .prefix((&collection, &token_id))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, offer)| offer))
+        .collect()
+}
+
+fn query_offers_by_bidder(
+    deps: Deps,
+    bidder: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Offer>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let start = start_after
+        .map(|(collection, token_id)| Bound::exclusive((collection, token_id, bidder.clone())));
+
+    offers()
+        .idx
+        .bidder
+        .prefix(bidder)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, offer)| offer))
+        .collect()
+}
+
+fn query_best_collection_offer(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    denom: Option<Denom>,
+) -> StdResult<Option<CollectionOffer>> {
+    let config = CONFIG.load(deps.storage)?;
+    let denom = denom.unwrap_or(Denom::Native(config.native_denom));
+    let now = Uint64::from(env.block.time.seconds());
+
+    // Walk the price index from the highest bid down to the first live offer in `denom`
+    for item in collection_offers().idx.price.sub_prefix(collection).range(
+        deps.storage,
+        None,
+        None,
+        Order::Descending,
+    ) {
+        let (_, offer) = item?;
+        if offer.denom == denom && offer.expires > now {
+            return Ok(Some(offer));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PauseState;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{ContractResult, SystemResult, WasmQuery};
+    use cw_utils::PaymentError;
+
+    const OWNER: &str = "owner";
+    const SELLER: &str = "seller";
+    const BUYER: &str = "buyer";
+    const COLLECTION: &str = "collection";
+    const CW20_TOKEN: &str = "cw20token";
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            owner: OWNER.to_string(),
+            collection_fabric_address: "fabric".to_string(),
+            native_denom: "uxion".to_string(),
+            royalty: 1,
+            protocol_fee_bps: 0,
+            fee_collector: None,
+            cw20_tokens: vec![CW20_TOKEN.to_string()],
+        };
+        instantiate(deps, mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    }
+
+    fn send_nft(
+        deps: DepsMut,
+        token_id: &str,
+        hook: Cw721HookMsg,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: SELLER.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+        execute(deps, mock_env(), mock_info(COLLECTION, &[]), msg)
+    }
+
+    fn list(
+        deps: DepsMut,
+        token_id: &str,
+        price: u128,
+        denom: Option<Denom>,
+    ) -> Result<Response, ContractError> {
+        let hook = Cw721HookMsg::SetListing {
+            price: Uint128::new(price),
+            denom,
+            expires_at: None,
+            reserved_for: None,
+        };
+        send_nft(deps, token_id, hook)
+    }
+
+    #[test]
+    fn test_buy_nft_with_cw20() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let denom = Denom::Cw20(Addr::unchecked(CW20_TOKEN));
+        list(deps.as_mut(), "1", 1000, Some(denom.clone())).unwrap();
+
+        // Native payments are rejected for a CW20 listing
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidDenom);
+
+        // The message cw20 `Send` delivers to the marketplace
+        let receive_msg = Cw20ReceiveMsg {
+            sender: BUYER.to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&Cw20HookMsg::BuyNft {
+                collection: COLLECTION.to_string(),
+                token_id: "1".to_string(),
+            })
+            .unwrap(),
+        };
+        let msg: ExecuteMsg = from_json(receive_msg.clone().into_binary().unwrap()).unwrap();
+        assert_eq!(msg, ExecuteMsg::Receive(receive_msg));
+        let res = execute(deps.as_mut(), mock_env(), mock_info(CW20_TOKEN, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[1].msg,
+            payment_msg(&denom, SELLER, Uint128::new(990)).unwrap()
+        );
+        assert_eq!(
+            res.messages[2].msg,
+            payment_msg(&denom, "fabric", Uint128::new(10)).unwrap()
+        );
+        assert!(!listings().has(&deps.storage, (COLLECTION, "1")));
+    }
+
+    #[test]
+    fn test_cw20_must_be_accepted() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let denom = Denom::Cw20(Addr::unchecked("othertoken"));
+        list(deps.as_mut(), "1", 1000, Some(denom)).unwrap_err();
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: BUYER.to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&Cw20HookMsg::BuyNft {
+                collection: COLLECTION.to_string(),
+                token_id: "1".to_string(),
+            })
+            .unwrap(),
+        });
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("othertoken", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Cw20TokenNotAccepted);
+    }
+
+    #[test]
+    fn test_offers() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let expires = Uint64::from(mock_env().block.time.seconds() + 100);
+
+        let make_offer = |price: u128| ExecuteMsg::MakeOffer {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(price),
+            expires,
+        };
+        let info = mock_info(BUYER, &[Coin::new(500, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), make_offer(400)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: Uint128::new(400),
+                received: Uint128::new(500)
+            }
+        );
+        execute(deps.as_mut(), mock_env(), info, make_offer(500)).unwrap();
+        let info = mock_info("other", &[Coin::new(300, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, make_offer(300)).unwrap();
+
+        let offers: Vec<Offer> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetOffersByToken {
+                    collection: COLLECTION.to_string(),
+                    token_id: "1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(offers.len(), 2);
+
+        // Live offers can only be retracted by their bidder
+        let retract = ExecuteMsg::RetractOffer {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            bidder: "other".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SELLER, &[]),
+            retract.clone(),
+        )
+        .unwrap_err();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(deps.as_mut(), env, mock_info(SELLER, &[]), retract).unwrap();
+
+        // The seller of the listing sells into the remaining offer
+        let accept = ExecuteMsg::AcceptOffer {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            bidder: BUYER.to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), accept).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            transfer_nft_msg(COLLECTION, BUYER, "1").unwrap()
+        );
+        assert!(!listings().has(&deps.storage, (COLLECTION, "1")));
+
+        let offers: Vec<Offer> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetOffersByBidder {
+                    bidder: BUYER.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(offers.is_empty());
+    }
+
+    #[test]
+    fn test_collection_offers() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&OwnerOfResponse {
+                    owner: SELLER.to_string(),
+                    approvals: vec![],
+                })
+                .unwrap(),
+            )),
+            _ => panic!("unexpected query"),
+        });
+        let expires = Uint64::from(mock_env().block.time.seconds() + 100);
+
+        let make_offer = |price: u128, quantity: u32| ExecuteMsg::MakeCollectionOffer {
+            collection: COLLECTION.to_string(),
+            price: Uint128::new(price),
+            quantity,
+            expires,
+        };
+        let info = mock_info(BUYER, &[Coin::new(200, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info.clone(), make_offer(100, 1)).unwrap_err();
+        execute(deps.as_mut(), mock_env(), info, make_offer(100, 2)).unwrap();
+        let info = mock_info("other", &[Coin::new(150, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, make_offer(150, 1)).unwrap();
+        let offer_escrow = |deps: Deps| NATIVE_OFFER_ESCROW.load(deps.storage, "uxion").unwrap();
+        assert_eq!(offer_escrow(deps.as_ref()), Uint128::new(350));
+
+        let best: Option<CollectionOffer> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetBestCollectionOffer {
+                    collection: COLLECTION.to_string(),
+                    denom: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(best.unwrap().bidder, Addr::unchecked("other"));
+
+        let accept = |token_id: &str, caller: &str| {
+            let msg = ExecuteMsg::AcceptCollectionOffer {
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+                bidder: BUYER.to_string(),
+            };
+            (mock_info(caller, &[]), msg)
+        };
+        let (info, msg) = accept("1", BUYER);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotAcceptOwnOffer);
+        let (info, msg) = accept("1", SELLER);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let offer = collection_offers()
+            .load(&deps.storage, (COLLECTION, &Addr::unchecked(BUYER)))
+            .unwrap();
+        assert_eq!(offer.quantity, 1);
+        assert_eq!(offer_escrow(deps.as_ref()), Uint128::new(250));
+
+        // Cancelling refunds the unfilled part only
+        let msg = ExecuteMsg::CancelCollectionOffer {
+            collection: COLLECTION.to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(BUYER, &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            payment_msg(
+                &Denom::Native("uxion".to_string()),
+                BUYER,
+                Uint128::new(100)
+            )
+            .unwrap()
+        );
+        assert_eq!(offer_escrow(deps.as_ref()), Uint128::new(150));
+    }
+
+    #[test]
+    fn test_list_listings() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        for token_id in ["1", "2", "3"] {
+            list(deps.as_mut(), token_id, 1000, None).unwrap();
+        }
+
+        let list_listings = |start_after: Option<(String, String)>| -> Vec<Listing> {
+            let msg = QueryMsg::ListListings {
+                collection: Some(COLLECTION.to_string()),
+                start_after,
+                limit: Some(2),
+            };
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+        };
+        let page = list_listings(None);
+        assert_eq!(page.len(), 2);
+        let last = page.last().unwrap();
+        let page = list_listings(Some((last.collection.clone(), last.token_id.clone())));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].token_id, "3");
+
+        let msg = QueryMsg::ListingsBySeller {
+            seller: SELLER.to_string(),
+            start_after: Some((COLLECTION.to_string(), "1".to_string())),
+            limit: None,
+        };
+        let by_seller: Vec<Listing> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(by_seller.len(), 2);
+    }
+
+    #[test]
+    fn test_list_auctions_by_seller_and_bidder() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds());
+        for token_id in ["1", "2", "3"] {
+            let hook = Cw721HookMsg::SetAuctionListing {
+                start_price: Uint128::new(100),
+                min_bid_step: Uint128::new(10),
+                start_time,
+                end_time: start_time + Uint64::new(100),
+                anti_sniping: None,
+                reserve_price: None,
+                hide_reserve_price: false,
+                buy_now_price: None,
+            };
+            send_nft(deps.as_mut(), token_id, hook).unwrap();
+        }
+        let bid = |deps: DepsMut, bidder: &str, token_id: &str, price: u128| {
+            let msg = ExecuteMsg::BidNft {
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+                price: Uint128::new(price),
+            };
+            let info = mock_info(bidder, &[Coin::new(price, "uxion")]);
+            execute(deps, mock_env(), info, msg).unwrap();
+        };
+        bid(deps.as_mut(), "bidder", "1", 100);
+        bid(deps.as_mut(), "bidder", "2", 100);
+
+        let msg = QueryMsg::AuctionsBySeller {
+            seller: SELLER.to_string(),
+            start_after: Some((COLLECTION.to_string(), "1".to_string())),
+            limit: None,
+        };
+        let by_seller: Vec<Auction> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(by_seller.len(), 2);
+        assert_eq!(by_seller[0].token_id, "2");
+
+        let by_bidder = |deps: Deps, bidder: &str| -> Vec<String> {
+            let msg = QueryMsg::AuctionsByBidder {
+                bidder: bidder.to_string(),
+                start_after: None,
+                limit: None,
+            };
+            let auctions: Vec<Auction> = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+            auctions
+                .into_iter()
+                .map(|auction| auction.token_id)
+                .collect()
+        };
+        assert_eq!(by_bidder(deps.as_ref(), "bidder"), vec!["1", "2"]);
+        assert!(by_bidder(deps.as_ref(), BUYER).is_empty());
+
+        // An outbid auction moves to the new highest bidder
+        bid(deps.as_mut(), BUYER, "2", 110);
+        assert_eq!(by_bidder(deps.as_ref(), "bidder"), vec!["1"]);
+        assert_eq!(by_bidder(deps.as_ref(), BUYER), vec!["2"]);
+
+        // Claimed auctions stay listed for their last bidder, as ended
+        let msg = ExecuteMsg::ClaimNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(deps.as_mut(), env, mock_info("bidder", &[]), msg).unwrap();
+        let msg = QueryMsg::AuctionsByBidder {
+            bidder: "bidder".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let auctions: Vec<Auction> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(auctions.len(), 1);
+        assert_eq!(auctions[0].token_id, "1");
+        assert_eq!(auctions[0].status, AuctionStatus::Ended);
+    }
+
+    #[test]
+    fn test_update_listing_price() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let err = list(deps.as_mut(), "1", 0, None).unwrap_err();
+        assert_eq!(err, ContractError::ZeroPrice);
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+
+        let msg = ExecuteMsg::UpdateListingPrice {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(1500),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(BUYER, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let event = &res.events[0];
+        assert_eq!(event.attributes[3].value, "1000");
+        assert_eq!(event.attributes[4].value, "1500");
+
+        let listing = listings().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(listing.price, Uint128::new(1500));
+    }
+
+    #[test]
+    fn test_return_expired_listings() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let hook = Cw721HookMsg::SetListing {
+            price: Uint128::new(1000),
+            denom: None,
+            expires_at: Some(Uint64::from(mock_env().block.time.seconds() + 100)),
+            reserved_for: None,
+        };
+        send_nft(deps.as_mut(), "2", hook).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::ListingExpired);
+
+        let msg = ExecuteMsg::ReturnExpiredListings { limit: None };
+        let res = execute(deps.as_mut(), env, mock_info(BUYER, &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            transfer_nft_msg(COLLECTION, SELLER, "2").unwrap()
+        );
+        assert_eq!(res.messages.len(), 1);
+        assert!(listings().has(&deps.storage, (COLLECTION, "1")));
+        assert!(!listings().has(&deps.storage, (COLLECTION, "2")));
+        assert_eq!(STATE.load(&deps.storage).unwrap().listing_count, 1);
+    }
+
+    #[test]
+    fn test_private_listing() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let hook = Cw721HookMsg::SetListing {
+            price: Uint128::new(1000),
+            denom: None,
+            expires_at: None,
+            reserved_for: Some(BUYER.to_string()),
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+
+        let msg = QueryMsg::PrivateListings {
+            buyer: BUYER.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let private: Vec<Listing> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(private.len(), 1);
+
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info("other", &[Coin::new(1000, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ListingReserved);
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_bundle() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&OwnerOfResponse {
+                    owner: SELLER.to_string(),
+                    approvals: vec![],
+                })
+                .unwrap(),
+            ))
+        });
+
+        let msg = ExecuteMsg::ListBundle {
+            items: vec![
+                (COLLECTION.to_string(), "1".to_string()),
+                ("collection2".to_string(), "1".to_string()),
+            ],
+            price: Uint128::new(1000),
+            denom: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(DEPOSITS.has(&deps.storage, ("collection2", SELLER, "1")));
+
+        // Bundle items can't be pulled out one by one
+        let msg = ExecuteMsg::CancelListing {
+            collection: "collection2".to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotListed);
+
+        let msg = ExecuteMsg::BuyBundle { bundle_id: 1 };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let denom = Denom::Native("uxion".to_string());
+        let expected = vec![
+            transfer_nft_msg(COLLECTION, BUYER, "1").unwrap(),
+            transfer_nft_msg("collection2", BUYER, "1").unwrap(),
+            payment_msg(&denom, SELLER, Uint128::new(990)).unwrap(),
+            payment_msg(&denom, "fabric", Uint128::new(5)).unwrap(),
+            payment_msg(&denom, "fabric", Uint128::new(5)).unwrap(),
+        ];
+        let messages: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(messages, expected);
+        assert!(!BUNDLES.has(&deps.storage, 1));
+        assert!(!DEPOSITS.has(&deps.storage, (COLLECTION, SELLER, "1")));
+    }
+
+    #[test]
+    fn test_buy_batch() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        list(deps.as_mut(), "2", 500, None).unwrap();
+        let asks = vec![
+            (COLLECTION.to_string(), "3".to_string()),
+            (COLLECTION.to_string(), "1".to_string()),
+            (COLLECTION.to_string(), "2".to_string()),
+        ];
+
+        // A missing listing fails the whole batch by default
+        let msg = ExecuteMsg::BuyBatch {
+            asks: asks.clone(),
+            allow_partial: false,
+        };
+        let info = mock_info(BUYER, &[Coin::new(2000, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NotListed);
+
+        // In partial mode it is skipped and refunded
+        let msg = ExecuteMsg::BuyBatch {
+            asks,
+            allow_partial: true,
+        };
+        let info = mock_info(BUYER, &[Coin::new(2000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let denom = Denom::Native("uxion".to_string());
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(
+            res.messages[2].msg,
+            payment_msg(&denom, "fabric", Uint128::new(10)).unwrap()
+        );
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            payment_msg(&denom, BUYER, Uint128::new(500)).unwrap()
+        );
+        assert_eq!(STATE.load(&deps.storage).unwrap().listing_count, 0);
+    }
+
+    #[test]
+    fn test_receive_nft_from_send_nft() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // The message cw721 `SendNft` delivers to the marketplace
+        let receive_msg = Cw721ReceiveMsg {
+            sender: SELLER.to_string(),
+            token_id: "1".to_string(),
+            msg: to_json_binary(&Cw721HookMsg::SetListing {
+                price: Uint128::new(1000),
+                denom: None,
+                expires_at: None,
+                reserved_for: None,
+            })
+            .unwrap(),
+        };
+        let msg: ExecuteMsg = from_json(receive_msg.clone().into_binary().unwrap()).unwrap();
+        assert_eq!(msg, ExecuteMsg::ReceiveNft(receive_msg));
+
+        execute(deps.as_mut(), mock_env(), mock_info(COLLECTION, &[]), msg).unwrap();
+        let listing = listings().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(listing.seller, SELLER);
+    }
+
+    #[test]
+    fn test_receive_nft() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // The collection is the caller and the seller is the sender of the NFT
+        let res = list(deps.as_mut(), "1", 1000, None).unwrap();
+        assert!(res.messages.is_empty());
+        let listing = listings().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(listing.seller, SELLER);
+
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        send_nft(deps.as_mut(), "2", hook).unwrap();
+        let auction = auctions().load(&deps.storage, (COLLECTION, "2")).unwrap();
+        assert_eq!(auction.seller, SELLER);
+        assert_eq!(auction.status, AuctionStatus::WaitingAuction);
+
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: SELLER.to_string(),
+            token_id: "3".to_string(),
+            msg: Binary::from(b"{}".to_vec()),
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(COLLECTION, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidHookMsg);
+    }
+
+    #[test]
+    fn test_royalty() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        // Only COLLECTION implements CW-2981, with a 5% royalty to its creator
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == COLLECTION => {
+                let Cw2981QueryMsg::RoyaltyInfo { sale_price, .. } = from_json(msg).unwrap();
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&RoyaltiesInfoResponse {
+                        address: "creator".to_string(),
+                        royalty_amount: sale_price.multiply_ratio(5u128, 100u128),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Ok(ContractResult::Err("not supported".to_string())),
+        });
+
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let denom = Denom::Native("uxion".to_string());
+        assert_eq!(
+            res.messages[1].msg,
+            payment_msg(&denom, SELLER, Uint128::new(950)).unwrap()
+        );
+        assert_eq!(
+            res.messages[2].msg,
+            payment_msg(&denom, "creator", Uint128::new(50)).unwrap()
+        );
+
+        // Other collections use their default, then the marketplace royalty
+        let msg = ExecuteMsg::SetCollectionRoyalty {
+            collection: "collection2".to_string(),
+            recipient: "artist".to_string(),
+            royalty: Uint128::new(3),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SELLER, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let royalty = |collection: &str| -> RoyaltiesInfoResponse {
+            let msg = QueryMsg::GetRoyaltyInfo {
+                collection: collection.to_string(),
+                token_id: "1".to_string(),
+                sale_price: Uint128::new(1000),
+            };
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            royalty("collection2"),
+            RoyaltiesInfoResponse {
+                address: "artist".to_string(),
+                royalty_amount: Uint128::new(30),
+            }
+        );
+        assert_eq!(
+            royalty("collection3"),
+            RoyaltiesInfoResponse {
+                address: "fabric".to_string(),
+                royalty_amount: Uint128::new(10),
+            }
+        );
+    }
+
+    #[test]
+    fn test_protocol_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: OWNER.to_string(),
+            collection_fabric_address: "fabric".to_string(),
+            native_denom: "uxion".to_string(),
+            royalty: 1,
+            protocol_fee_bps: 250,
+            fee_collector: Some("treasury".to_string()),
+            cw20_tokens: vec![],
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let msg = QueryMsg::GetFeeSchedule {};
+        let schedule: FeeScheduleResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            schedule,
+            FeeScheduleResponse {
+                protocol_fee_bps: 250,
+                fee_collector: Addr::unchecked("treasury"),
+                default_royalty: 1,
+                default_royalty_recipient: Addr::unchecked("fabric"),
+            }
+        );
+
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let denom = Denom::Native("uxion".to_string());
+        let expected = vec![
+            transfer_nft_msg(COLLECTION, BUYER, "1").unwrap(),
+            payment_msg(&denom, SELLER, Uint128::new(965)).unwrap(),
+            payment_msg(&denom, "treasury", Uint128::new(25)).unwrap(),
+            payment_msg(&denom, "fabric", Uint128::new(10)).unwrap(),
+        ];
+        let messages: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(messages, expected);
+        let event = &res.events[0];
+        assert!(event.attributes.contains(&attr("protocol_fee", "25")));
+        assert!(event.attributes.contains(&attr("royalty", "10")));
+        assert!(event
+            .attributes
+            .contains(&attr("royalty_recipient", "fabric")));
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let update =
+            |native_denom: Option<&str>, protocol_fee_bps: Option<u64>| ExecuteMsg::UpdateConfig {
+                native_denom: native_denom.map(str::to_string),
+                royalty: None,
+                protocol_fee_bps,
+                fee_collector: Some("treasury".to_string()),
+                collection_fabric_address: None,
+                strict_collections: None,
+            };
+
+        let msg = update(None, Some(100));
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let msg = update(Some("1x"), None);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidNativeDenom {
+                denom: "1x".to_string()
+            }
+        );
+        let msg = update(None, Some(1_001));
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidProtocolFee { max: 1_000 });
+        TOTAL_AUCTION_ESCROW
+            .save(deps.as_mut().storage, &Uint128::new(100))
+            .unwrap();
+        let msg = update(Some("ibc/27394FB0"), None);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::AuctionEscrowOutstanding);
+        TOTAL_AUCTION_ESCROW
+            .save(deps.as_mut().storage, &Uint128::zero())
+            .unwrap();
+
+        let msg = update(Some("ibc/27394FB0"), Some(100));
+        let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let event = &res.events[0];
+        assert_eq!(event.ty, "config_updated");
+        assert!(event
+            .attributes
+            .contains(&attr("old_native_denom", "uxion")));
+        assert!(event
+            .attributes
+            .contains(&attr("new_native_denom", "ibc/27394FB0")));
+        assert!(event.attributes.contains(&attr("old_fee_collector", OWNER)));
+        assert!(event
+            .attributes
+            .contains(&attr("new_protocol_fee_bps", "100")));
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+        assert_eq!(config.native_denom, "ibc/27394FB0");
+        assert_eq!(config.protocol_fee_bps, 100);
+        assert_eq!(config.fee_collector, Addr::unchecked("treasury"));
+        assert_eq!(config.royalty, 1);
+    }
+
+    #[test]
+    fn test_ownership() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let propose = |expiry: Option<Expiration>| ExecuteMsg::ProposeNewOwner {
+            owner: "newowner".to_string(),
+            expiry,
+        };
+
+        let msg = propose(None);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        // The proposal can only be accepted by the proposed owner before it expires
+        let expiry = Expiration::AtHeight(mock_env().block.height + 10);
+        let msg = propose(Some(expiry));
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let accept = ExecuteMsg::AcceptOwnership {};
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SELLER, &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let mut env = mock_env();
+        env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("newowner", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OwnershipProposalExpired);
+
+        let msg = ExecuteMsg::CancelOwnershipProposal {};
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("newowner", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoOwnershipProposal);
+
+        let msg = propose(None);
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("newowner", &[]),
+            accept,
+        )
+        .unwrap();
+        let ownership: OwnershipResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetOwnership {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            ownership,
+            OwnershipResponse {
+                owner: Some(Addr::unchecked("newowner")),
+                pending_owner: None,
+                pending_expiry: None,
+            }
+        );
+        let msg = ExecuteMsg::AddAdmin {
+            account_id: Addr::unchecked("admin"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), msg).unwrap();
+
+        // Once renounced, owner-gated operations are closed for good
+        let msg = ExecuteMsg::RenounceOwnership {};
+        execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AddCw20Token {
+            token: "othertoken".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NoOwner);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+
+        let msg = ExecuteMsg::Pause {
+            operations: vec![TradingOperation::Buying],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SELLER, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let pause_state: PauseState =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetPauseState {}).unwrap())
+                .unwrap();
+        assert!(pause_state.buying && !pause_state.listing);
+
+        // Buying is stopped, other operations and cancels keep working
+        let buy = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Paused {
+                operation: TradingOperation::Buying
+            }
+        );
+        list(deps.as_mut(), "2", 1000, None).unwrap();
+        let msg = ExecuteMsg::CancelListing {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Unpause {
+            operations: vec![TradingOperation::Buying],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_from_v0_1() {
+        use crate::migrations::{
+            AuctionV0_1, ConfigV0_1, ListingV0_1, ADMINS_V0_1, AUCTIONS_V0_1, CONFIG_V0_1,
+            LISTINGS_V0_1,
+        };
+
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+
+        // Storage as 0.1.0 wrote it
+        let legacy_config = ConfigV0_1 {
+            native_denom: "uxion".to_string(),
+            royalty: 1,
+        };
+        CONFIG_V0_1
+            .save(deps.as_mut().storage, &legacy_config)
+            .unwrap();
+        let legacy_listing = ListingV0_1 {
+            seller: SELLER.to_string(),
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(1000),
+            royalty: Uint128::new(10),
+        };
+        LISTINGS_V0_1
+            .save(deps.as_mut().storage, (COLLECTION, "1"), &legacy_listing)
+            .unwrap();
+        let start_time = Uint64::from(mock_env().block.time.seconds());
+        let mut legacy_auction = AuctionV0_1 {
+            seller: SELLER.to_string(),
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            current_price: Uint128::new(100),
+            current_bidder: Some(Addr::unchecked(BUYER)),
+            status: AuctionStatus::InAuction,
+            royalty: Uint128::new(1),
+        };
+        AUCTIONS_V0_1
+            .save(deps.as_mut().storage, (COLLECTION, "2"), &legacy_auction)
+            .unwrap();
+        // Claimed in 0.1.0, which left its deposit behind
+        let claimed_auction = AuctionV0_1 {
+            token_id: "3".to_string(),
+            status: AuctionStatus::Ended,
+            ..legacy_auction.clone()
+        };
+        AUCTIONS_V0_1
+            .save(deps.as_mut().storage, (COLLECTION, "3"), &claimed_auction)
+            .unwrap();
+        for token_id in ["1", "2", "3"] {
+            let deposit = Deposits {
+                owner: SELLER.to_string(),
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+            };
+            DEPOSITS
+                .save(
+                    deps.as_mut().storage,
+                    (COLLECTION, SELLER, token_id),
+                    &deposit,
+                )
+                .unwrap();
+        }
+        ADMINS_V0_1
+            .save(deps.as_mut().storage, &vec![Addr::unchecked("admin")])
+            .unwrap();
+
+        // Open auctions with a bid have nothing escrowed and must be settled first
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnescrowedAuctionBid {
+                collection: COLLECTION.to_string(),
+                token_id: "2".to_string()
+            }
+        );
+        legacy_auction.current_bidder = None;
+        AUCTIONS_V0_1
+            .save(deps.as_mut().storage, (COLLECTION, "2"), &legacy_auction)
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1], attr("from_version", "0.1.0"));
+
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.protocol_fee_bps, 0);
+        assert_eq!(config.fee_collector, Addr::unchecked(OWNER));
+        let listing = listings()
+            .load(deps.as_ref().storage, (COLLECTION, "1"))
+            .unwrap();
+        assert_eq!(listing.denom, Denom::Native("uxion".to_string()));
+        let by_seller = listings()
+            .idx
+            .seller
+            .prefix(SELLER.to_string())
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(by_seller, 1);
+        let auction = auctions()
+            .load(deps.as_ref().storage, (COLLECTION, "2"))
+            .unwrap();
+        assert_eq!(auction.kind, AuctionKind::English);
+        assert_eq!(auction.status, AuctionStatus::InAuction);
+        let by_seller = auctions()
+            .idx
+            .seller
+            .prefix(SELLER.to_string())
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(by_seller, 2);
+
+        // Only the deposits of NFTs the marketplace still holds are kept
+        assert!(DEPOSITS.has(deps.as_ref().storage, (COLLECTION, SELLER, "1")));
+        assert!(DEPOSITS.has(deps.as_ref().storage, (COLLECTION, SELLER, "2")));
+        assert!(!DEPOSITS.has(deps.as_ref().storage, (COLLECTION, SELLER, "3")));
+        assert!(is_admin(deps.as_ref(), Addr::unchecked("admin")).unwrap());
+        assert_eq!(ADMINS_V0_1.may_load(deps.as_ref().storage).unwrap(), None);
+        let version = get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Downgrades and foreign contracts are refused
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrateDowngrade {
+                stored: "99.0.0".to_string(),
+                current: CONTRACT_VERSION.to_string()
+            }
+        );
+        set_contract_version(deps.as_mut().storage, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidContractName {
+                name: "crates.io:other".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_lists_registered_collections_only() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let msg = ExecuteMsg::UpdateConfig {
+            native_denom: None,
+            royalty: None,
+            protocol_fee_bps: None,
+            fee_collector: None,
+            collection_fabric_address: None,
+            strict_collections: Some(true),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let err = list(deps.as_mut(), "1", 1000, None).unwrap_err();
+        assert_eq!(err, ContractError::CollectionNotRegistered);
+
+        // Only the owner or admins manage the registry
+        let register = |collection: &str, verified: bool| ExecuteMsg::RegisterCollection {
+            collection: collection.to_string(),
+            name: format!("{collection} name"),
+            creator: "creator".to_string(),
+            verified,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SELLER, &[]),
+            register(COLLECTION, true),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let msg = ExecuteMsg::AddAdmin {
+            account_id: Addr::unchecked("admin"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        for (collection, verified) in [(COLLECTION, true), ("collection2", false)] {
+            let info = mock_info("admin", &[]);
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                register(collection, verified),
+            )
+            .unwrap();
+        }
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+
+        let query_collections = |deps: Deps, verified_only: bool| -> Vec<CollectionInfo> {
+            let msg = QueryMsg::ListCollections {
+                verified_only,
+                start_after: None,
+                limit: None,
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(query_collections(deps.as_ref(), false).len(), 2);
+        let verified = query_collections(deps.as_ref(), true);
+        assert_eq!(verified.len(), 1);
+        assert_eq!(verified[0].collection, Addr::unchecked(COLLECTION));
+        assert_eq!(verified[0].name, "collection name");
+
+        let msg = ExecuteMsg::UnregisterCollection {
+            collection: COLLECTION.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let msg = QueryMsg::GetCollection {
+            collection: COLLECTION.to_string(),
+        };
+        assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+        let err = list(deps.as_mut(), "2", 1000, None).unwrap_err();
+        assert_eq!(err, ContractError::CollectionNotRegistered);
+    }
+
+    #[test]
+    fn test_dutch_auction() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let hook = Cw721HookMsg::SetDutchAuctionListing {
+            start_price: Uint128::new(1000),
+            floor_price: Uint128::new(200),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            curve: PriceCurve::Stepped {
+                interval: Uint64::new(25),
+            },
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+
+        let at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(10 + seconds);
+            env
+        };
+        let price_at = |deps: Deps, seconds: u64| -> Uint128 {
+            let msg = QueryMsg::GetCurrentDutchPrice {
+                collection: COLLECTION.to_string(),
+                token_id: "1".to_string(),
+            };
+            from_json(query(deps, at(seconds), msg).unwrap()).unwrap()
+        };
+        assert_eq!(price_at(deps.as_ref(), 0), Uint128::new(1000));
+        assert_eq!(price_at(deps.as_ref(), 45), Uint128::new(800));
+        assert_eq!(price_at(deps.as_ref(), 60), Uint128::new(600));
+        assert_eq!(price_at(deps.as_ref(), 500), Uint128::new(200));
+
+        // Dutch auctions are bought, not started and bid on
+        let msg = ExecuteMsg::StartAuction {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), at(0), mock_info(SELLER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotEnglishAuction);
+
+        let buy = ExecuteMsg::BuyDutchAuction {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotActive);
+        let info = mock_info(BUYER, &[Coin::new(500, "uxion")]);
+        let err = execute(deps.as_mut(), at(60), info, buy.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: Uint128::new(600),
+                received: Uint128::new(500)
+            }
+        );
+
+        // The buyer pays the current price and gets the rest back
+        let info = mock_info(BUYER, &[Coin::new(700, "uxion")]);
+        let res = execute(deps.as_mut(), at(60), info, buy.clone()).unwrap();
+        let payments: Vec<(String, u128)> = res
+            .messages
+            .iter()
+            .filter_map(|msg| match &msg.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    Some((to_address.clone(), amount[0].amount.u128()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            payments,
+            vec![
+                (SELLER.to_string(), 594),
+                ("fabric".to_string(), 6),
+                (BUYER.to_string(), 100)
+            ]
+        );
+        let auction = auctions().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(auction.status, AuctionStatus::Ended);
+        assert_eq!(auction.current_bidder, Some(Addr::unchecked(BUYER)));
+
+        let info = mock_info("other", &[Coin::new(700, "uxion")]);
+        let err = execute(deps.as_mut(), at(61), info, buy).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotActive);
+    }
+
+    #[test]
+    fn test_late_bids_extend_auction() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let msg = ExecuteMsg::SetDefaultAntiSniping {
+            anti_sniping: Some(AntiSniping {
+                extension_window: Uint64::new(10),
+                extension_duration: Uint64::new(30),
+                max_extension: Some(Uint64::new(40)),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetDefaultAntiSniping {
+            anti_sniping: Some(AntiSniping {
+                extension_window: Uint64::new(10),
+                extension_duration: Uint64::MAX,
+                max_extension: None,
+            }),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidAntiSniping);
+
+        // The cap can't overflow past the largest end time
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: Uint64::MAX,
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        let err = send_nft(deps.as_mut(), "1", hook).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+        let at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(10 + seconds);
+            env
+        };
+        let msg = ExecuteMsg::StartAuction {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), at(0), mock_info(SELLER, &[]), msg).unwrap();
+
+        let bid = |deps: DepsMut, seconds: u64, price: u128| {
+            let msg = ExecuteMsg::BidNft {
+                collection: COLLECTION.to_string(),
+                token_id: "1".to_string(),
+                price: Uint128::new(price),
+            };
+            let info = mock_info(BUYER, &[Coin::new(price, "uxion")]);
+            execute(deps, at(seconds), info, msg)
+        };
+        let end_time = |deps: Deps| {
+            let msg = QueryMsg::GetAuctionByCollectionTokenID {
+                collection: COLLECTION.to_string(),
+                token_id: "1".to_string(),
+            };
+            let auction: Auction = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+            (auction.end_time - start_time).u64()
+        };
+
+        // Bids before the window leave the end untouched
+        let res = bid(deps.as_mut(), 50, 100).unwrap();
+        assert!(res.events.is_empty());
+        assert_eq!(end_time(deps.as_ref()), 100);
+
+        let res = bid(deps.as_mut(), 95, 110).unwrap();
+        assert_eq!(res.events[0].ty, "auction_extended");
+        assert_eq!(end_time(deps.as_ref()), 125);
+
+        // Extensions stop at the cap
+        bid(deps.as_mut(), 120, 120).unwrap();
+        assert_eq!(end_time(deps.as_ref()), 140);
+        let err = bid(deps.as_mut(), 140, 130).unwrap_err();
+        assert_eq!(err, ContractError::AuctionEnded);
+    }
+
+    #[test]
+    fn test_auction_below_reserve_is_refunded() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(10 + seconds);
+            env
+        };
+        let auction_msg = |token_id: &str, method: &str| match method {
+            "start" => ExecuteMsg::StartAuction {
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+            },
+            "bid" => ExecuteMsg::BidNft {
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+                price: Uint128::new(300),
+            },
+            _ => ExecuteMsg::ClaimNft {
+                collection: COLLECTION.to_string(),
+                token_id: token_id.to_string(),
+            },
+        };
+
+        // A hidden reserve above the winning bid and a public one below it
+        for (token_id, reserve_price, hide_reserve_price) in [("1", 500, true), ("2", 200, false)] {
+            let hook = Cw721HookMsg::SetAuctionListing {
+                start_price: Uint128::new(100),
+                min_bid_step: Uint128::new(10),
+                start_time,
+                end_time: start_time + Uint64::new(100),
+                anti_sniping: None,
+                reserve_price: Some(Uint128::new(reserve_price)),
+                hide_reserve_price,
+                buy_now_price: None,
+            };
+            send_nft(deps.as_mut(), token_id, hook).unwrap();
+            let info = mock_info(SELLER, &[]);
+            execute(deps.as_mut(), at(0), info, auction_msg(token_id, "start")).unwrap();
+            let info = mock_info(BUYER, &[Coin::new(300, "uxion")]);
+            execute(deps.as_mut(), at(50), info, auction_msg(token_id, "bid")).unwrap();
+        }
+
+        let msg = QueryMsg::GetAuctionByCollectionTokenID {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let auction: Auction = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(auction.reserve_price, None);
+        assert!(auction.reserve_hidden);
+
+        // The bid is refunded and the NFT goes back to the seller
+        let info = mock_info(BUYER, &[]);
+        let res = execute(deps.as_mut(), at(100), info, auction_msg("1", "claim")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BUYER.to_string(),
+                amount: vec![Coin::new(300, "uxion")],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            transfer_nft_msg(COLLECTION, SELLER, "1").unwrap()
+        );
+        assert_eq!(res.events[0].attributes[2], attr("reserve_met", "false"));
+        let auction = auctions().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(auction.reserve_met, Some(false));
+
+        let info = mock_info(BUYER, &[]);
+        let res = execute(deps.as_mut(), at(100), info, auction_msg("2", "claim")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            transfer_nft_msg(COLLECTION, BUYER, "2").unwrap()
+        );
+        let auction = auctions().load(&deps.storage, (COLLECTION, "2")).unwrap();
+        assert_eq!(auction.reserve_met, Some(true));
+
+        // Neither NFT is held for the seller anymore, the first one can be listed again
+        assert!(!DEPOSITS.has(&deps.storage, (COLLECTION, SELLER, "1")));
+        assert!(!DEPOSITS.has(&deps.storage, (COLLECTION, SELLER, "2")));
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+    }
+
+    #[test]
+    fn test_buy_now_ends_auction() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds());
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: Some(Uint128::new(1000)),
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+        let msg = ExecuteMsg::StartAuction {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+        let msg = ExecuteMsg::BidNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(300),
+        };
+        let info = mock_info("bidder", &[Coin::new(300, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let buy_now = ExecuteMsg::BuyNow {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(900, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, buy_now.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: Uint128::new(1000),
+                received: Uint128::new(900)
+            }
+        );
+
+        // The bidder is refunded and the sale settles without a claim
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, buy_now).unwrap();
+        let msgs: Vec<CosmosMsg> = res.messages.into_iter().map(|msg| msg.msg).collect();
+        let bank_send = |to_address: &str, amount: u128| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: to_address.to_string(),
+                amount: vec![Coin::new(amount, "uxion")],
+            })
+        };
+        assert_eq!(
+            msgs,
+            vec![
+                bank_send("bidder", 300),
+                transfer_nft_msg(COLLECTION, BUYER, "1").unwrap(),
+                bank_send(SELLER, 990),
+                bank_send("fabric", 10),
+            ]
+        );
+        let auction = auctions().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(auction.status, AuctionStatus::Ended);
+        assert_eq!(auction.current_bidder, Some(Addr::unchecked(BUYER)));
+
+        let msg = ExecuteMsg::ClaimNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = execute(deps.as_mut(), env, mock_info(BUYER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotActive);
+
+        // Buy-now closes once the bids reach its price
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: Some(Uint128::new(500)),
+        };
+        send_nft(deps.as_mut(), "2", hook).unwrap();
+        let msg = ExecuteMsg::BidNft {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+            price: Uint128::new(800),
+        };
+        let info = mock_info("bidder", &[Coin::new(800, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::BuyNow {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(500, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::BuyNowPriceReached);
+    }
+
+    #[test]
+    fn test_auction_status_follows_block_time() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds() + 10);
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+        let at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(10 + seconds);
+            env
+        };
+        let status_at = |deps: Deps, seconds: u64| {
+            let msg = QueryMsg::ListAuctions {
+                collection: None,
+                status: None,
+                start_after: None,
+                limit: None,
+            };
+            let auctions: Vec<Auction> = from_json(query(deps, at(seconds), msg).unwrap()).unwrap();
+            auctions[0].status.clone()
+        };
+        assert_eq!(status_at(deps.as_ref(), 0), AuctionStatus::InAuction);
+        assert_eq!(
+            status_at(deps.as_ref(), 100),
+            AuctionStatus::WaitingForClaim
+        );
+
+        // Once its start time passes the auction can't be cancelled, even without bids
+        let cancel = ExecuteMsg::CancelAuction {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), at(0), mock_info(SELLER, &[]), cancel).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotWaiting);
+
+        // Bids are only taken while the auction is live, without starting it
+        let bid = ExecuteMsg::BidNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(100),
+        };
+        let info = mock_info(BUYER, &[Coin::new(100, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), bid.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotActive);
+        execute(deps.as_mut(), at(0), info, bid).unwrap();
+
+        let claim = ExecuteMsg::ClaimNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[]);
+        let err = execute(deps.as_mut(), at(99), info.clone(), claim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotEnded);
+        let msg = QueryMsg::ListAuctions {
+            collection: Some(COLLECTION.to_string()),
+            status: Some(AuctionStatus::WaitingForClaim),
+            start_after: None,
+            limit: None,
+        };
+        let auctions: Vec<Auction> =
+            from_json(query(deps.as_ref(), at(100), msg).unwrap()).unwrap();
+        assert_eq!(auctions.len(), 1);
+        let res = execute(deps.as_mut(), at(100), info, claim).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            transfer_nft_msg(COLLECTION, BUYER, "1").unwrap()
+        );
+        assert_eq!(status_at(deps.as_ref(), 100), AuctionStatus::Ended);
+    }
+
+    #[test]
+    fn test_bids_are_escrowed() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let start_time = Uint64::from(mock_env().block.time.seconds());
+        let hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        send_nft(deps.as_mut(), "1", hook).unwrap();
+        let bid = |price: u128| ExecuteMsg::BidNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Uint128::new(price),
+        };
+
+        let info = mock_info("bidder", &[Coin::new(50, "uxion")]);
+        let err = execute(deps.as_mut(), mock_env(), info, bid(100)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: Uint128::new(100),
+                received: Uint128::new(50)
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder", &[]),
+            bid(100),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+
+        // Each bid is paid in and the one it beats is refunded from its escrow
+        let info = mock_info("bidder", &[Coin::new(100, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, bid(100)).unwrap();
+        assert!(res.messages.is_empty());
+        let info = mock_info(BUYER, &[Coin::new(120, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, bid(120)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bidder".to_string(),
+                amount: vec![Coin::new(100, "uxion")],
+            })
+        );
+
+        let msg = QueryMsg::GetAuctionEscrow {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let escrow: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(escrow, Uint128::new(120));
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(120, "uxion")]);
+        let invariant = |deps: Deps| -> EscrowInvariantResponse {
+            let msg = QueryMsg::GetEscrowInvariant {};
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(invariant(deps.as_ref()).total_escrow, Uint128::new(120));
+        assert!(invariant(deps.as_ref()).holds);
+
+        // Offers are held in the same balance
+        let msg = ExecuteMsg::MakeOffer {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+            price: Uint128::new(50),
+            expires: Uint64::from(mock_env().block.time.seconds() + 100),
+        };
+        let info = mock_info("bidder", &[Coin::new(50, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let escrow = invariant(deps.as_ref());
+        assert_eq!(escrow.auction_escrow, Uint128::new(120));
+        assert_eq!(escrow.offer_escrow, Uint128::new(50));
+        assert_eq!(escrow.total_escrow, Uint128::new(170));
+        assert!(!escrow.holds);
+        let msg = ExecuteMsg::CancelOffer {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg).unwrap();
+        assert!(invariant(deps.as_ref()).holds);
+
+        // Claiming pays the escrow out
+        let msg = ExecuteMsg::ClaimNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(deps.as_mut(), env, mock_info(BUYER, &[]), msg).unwrap();
+        assert_eq!(
+            TOTAL_AUCTION_ESCROW.load(&deps.storage).unwrap(),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn test_royalty_capped_by_protocol_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: OWNER.to_string(),
+            collection_fabric_address: "fabric".to_string(),
+            native_denom: "uxion".to_string(),
+            royalty: 100,
+            protocol_fee_bps: 1_000,
+            fee_collector: Some("treasury".to_string()),
+            cw20_tokens: vec![],
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let msg = ExecuteMsg::BuyNft {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let info = mock_info(BUYER, &[Coin::new(1000, "uxion")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let denom = Denom::Native("uxion".to_string());
+        let expected = vec![
+            transfer_nft_msg(COLLECTION, BUYER, "1").unwrap(),
+            payment_msg(&denom, "treasury", Uint128::new(100)).unwrap(),
+            payment_msg(&denom, "fabric", Uint128::new(900)).unwrap(),
+        ];
+        let messages: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(messages, expected);
+        let event = &res.events[0];
+        assert!(event.attributes.contains(&attr("royalty", "900")));
+    }
+
+    #[test]
+    fn test_receive_nft_refuses_listed_token() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list(deps.as_mut(), "1", 1000, None).unwrap();
+        let start_time = Uint64::from(mock_env().block.time.seconds());
+        let auction_hook = Cw721HookMsg::SetAuctionListing {
+            start_price: Uint128::new(100),
+            min_bid_step: Uint128::new(10),
+            start_time,
+            end_time: start_time + Uint64::new(100),
+            anti_sniping: None,
+            reserve_price: None,
+            hide_reserve_price: false,
+            buy_now_price: None,
+        };
+        send_nft(deps.as_mut(), "2", auction_hook.clone()).unwrap();
+        let msg = ExecuteMsg::BidNft {
+            collection: COLLECTION.to_string(),
+            token_id: "2".to_string(),
+            price: Uint128::new(100),
+        };
+        let info = mock_info(BUYER, &[Coin::new(100, "uxion")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Neither the listing nor the auction and its bid can be overwritten by another hook
+        let receive = |token_id: &str, hook: &Cw721HookMsg| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: "attacker".to_string(),
+                token_id: token_id.to_string(),
+                msg: to_json_binary(hook).unwrap(),
+            })
+        };
+        let sale_hook = Cw721HookMsg::SetListing {
+            price: Uint128::new(1),
+            denom: None,
+            expires_at: None,
+            reserved_for: None,
+        };
+        for (token_id, hook) in [("1", &sale_hook), ("2", &sale_hook), ("2", &auction_hook)] {
+            let msg = receive(token_id, hook);
+            let err =
+                execute(deps.as_mut(), mock_env(), mock_info(COLLECTION, &[]), msg).unwrap_err();
+            assert_eq!(err, ContractError::AlreadyListed);
+        }
+        let auction = auctions().load(&deps.storage, (COLLECTION, "2")).unwrap();
+        assert_eq!(auction.seller, SELLER);
+        assert_eq!(auction.current_bidder, Some(Addr::unchecked(BUYER)));
+    }
 }
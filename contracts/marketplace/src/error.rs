@@ -0,0 +1,135 @@
+use cosmwasm_std::{StdError, Uint128};
+use cw_utils::PaymentError;
+
+use crate::state::TradingOperation;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("{operation:?} is paused")]
+    Paused { operation: TradingOperation },
+    #[error("Cannot migrate from contract {name}")]
+    InvalidContractName { name: String },
+    #[error("Invalid contract version {version}")]
+    InvalidContractVersion { version: String },
+    #[error("Cannot migrate from version {stored} down to {current}")]
+    CannotMigrateDowngrade { stored: String, current: String },
+    #[error("Auction {collection} {token_id} has an unescrowed bid, settle it before migrating")]
+    UnescrowedAuctionBid {
+        collection: String,
+        token_id: String,
+    },
+    #[error("Contract has no owner")]
+    NoOwner,
+    #[error("No ownership proposal found")]
+    NoOwnershipProposal,
+    #[error("Ownership proposal has expired")]
+    OwnershipProposalExpired,
+    #[error("Invalid hook message")]
+    InvalidHookMsg,
+    #[error("Invalid funds: expected {expected}, received {received}")]
+    InvalidFunds {
+        expected: Uint128,
+        received: Uint128,
+    },
+    #[error("Invalid payment denom")]
+    InvalidDenom,
+    #[error("Invalid native denom: {denom}")]
+    InvalidNativeDenom { denom: String },
+    #[error("Native denom is not supported")]
+    UnsupportedNativeDenom,
+    #[error("Native denom can't change while auction bids are escrowed")]
+    AuctionEscrowOutstanding,
+    #[error("CW20 token is not accepted")]
+    Cw20TokenNotAccepted,
+    #[error("CW20 token is already accepted")]
+    Cw20TokenAlreadyAccepted,
+    #[error("Admin already exists")]
+    AdminExists,
+    #[error("Admin not found")]
+    AdminNotFound,
+    #[error("Protocol fee must be at most {max} bps")]
+    InvalidProtocolFee { max: u64 },
+    #[error("Collection is not registered")]
+    CollectionNotRegistered,
+    #[error("Royalty must be a percentage between 0 and 100")]
+    InvalidRoyalty,
+    #[error("Caller is not the NFT owner")]
+    NotNftOwner,
+    #[error("NFT is not listed for sale")]
+    NotListed,
+    #[error("NFT is already listed")]
+    AlreadyListed,
+    #[error("Price must be greater than zero")]
+    ZeroPrice,
+    #[error("Expiration must be in the future")]
+    InvalidExpiration,
+    #[error("Listing has expired")]
+    ListingExpired,
+    #[error("Listing is reserved for another buyer")]
+    ListingReserved,
+    #[error("You cannot buy your own NFT")]
+    CannotBuyOwnNft,
+    #[error("Auction not found")]
+    AuctionNotFound,
+    #[error("Auction is not active")]
+    AuctionNotActive,
+    #[error("Auction is not in waiting status")]
+    AuctionNotWaiting,
+    #[error("Auction has ended")]
+    AuctionEnded,
+    #[error("Auction has not ended")]
+    AuctionNotEnded,
+    #[error("Auction start time is before now")]
+    AuctionStartTimeIsBeforeNow,
+    #[error("Auction end time is before start time")]
+    AuctionEndTimeIsBeforeStartTime,
+    #[error("Minimum bid step must be greater than zero")]
+    ZeroMinBidStep,
+    #[error("Floor price must be below the start price")]
+    InvalidFloorPrice,
+    #[error("Price step interval must not be zero")]
+    ZeroPriceStepInterval,
+    #[error("Auction is not a Dutch auction")]
+    NotDutchAuction,
+    #[error("Dutch auctions can't be bid on")]
+    NotEnglishAuction,
+    #[error("Reserve price must not be below the start price")]
+    InvalidReservePrice,
+    #[error("Buy-now price must be above the start price and not below the reserve price")]
+    InvalidBuyNowPrice,
+    #[error("Auction has no buy-now price")]
+    NoBuyNowPrice,
+    #[error("Bids have reached the buy-now price")]
+    BuyNowPriceReached,
+    #[error(
+        "Anti-sniping window and duration must not be zero, and no setting may exceed 30 days"
+    )]
+    InvalidAntiSniping,
+    #[error("Bid price too low")]
+    BidTooLow,
+    #[error("Offer not found")]
+    OfferNotFound,
+    #[error("Offer already exists")]
+    OfferAlreadyExists,
+    #[error("Offer has expired")]
+    OfferExpired,
+    #[error("Offer has not expired")]
+    OfferNotExpired,
+    #[error("Offer quantity must be greater than zero")]
+    ZeroQuantity,
+    #[error("You cannot accept your own offer")]
+    CannotAcceptOwnOffer,
+    #[error("Bundle not found")]
+    BundleNotFound,
+    #[error("Bundle must hold between 2 and {max} items")]
+    InvalidBundleSize { max: usize },
+    #[error("Duplicate bundle item")]
+    DuplicateBundleItem,
+}
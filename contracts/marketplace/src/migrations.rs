@@ -0,0 +1,181 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128, Uint64};
+use cw20::Denom;
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::state::{
+    auctions, listings, Auction, AuctionKind, AuctionStatus, Config, Deposits, Listing, ADMINS,
+    BUNDLES, CONFIG, DEPOSITS, STATE,
+};
+
+/// A step upgrading the storage written by the previous version to the layout of `version`.
+pub struct Migration {
+    pub version: &'static str,
+    pub run: fn(&mut dyn Storage) -> Result<(), ContractError>,
+}
+
+/// Migration steps, in version order.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: "0.2.0",
+    run: v0_2_0,
+}];
+
+// Storage layout of 0.1.0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV0_1 {
+    pub native_denom: String,
+    pub royalty: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingV0_1 {
+    pub seller: String,
+    pub collection: String,
+    pub token_id: String,
+    pub price: Uint128,
+    pub royalty: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionV0_1 {
+    pub seller: String,
+    pub collection: String,
+    pub token_id: String,
+    pub start_price: Uint128,
+    pub min_bid_step: Uint128,
+    pub start_time: Uint64,
+    pub end_time: Uint64,
+    pub current_price: Uint128,
+    pub current_bidder: Option<Addr>,
+    pub status: AuctionStatus,
+    pub royalty: Uint128,
+}
+
+pub const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+pub const LISTINGS_V0_1: Map<(&str, &str), ListingV0_1> = Map::new("listings");
+pub const AUCTIONS_V0_1: Map<(&str, &str), AuctionV0_1> = Map::new("auctions");
+pub const ADMINS_V0_1: Item<Vec<Addr>> = Item::new("admins");
+
+/// 0.2.0 adds the protocol fee to `Config`, prices listings in a denom, indexes listings and
+/// auctions, keeps admins in a map instead of a single list and drops the deposits 0.1.0 left
+/// behind for claimed auctions.
+fn v0_2_0(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    // Bids of 0.1.0 weren't paid in, an open auction with a bid has nothing escrowed to settle it
+    let legacy_auctions = AUCTIONS_V0_1
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some((_, auction)) = legacy_auctions
+        .iter()
+        .find(|(_, auction)| auction.current_bidder.is_some() && is_open(&auction.status))
+    {
+        return Err(ContractError::UnescrowedAuctionBid {
+            collection: auction.collection.clone(),
+            token_id: auction.token_id.clone(),
+        });
+    }
+
+    let state = STATE.load(storage)?;
+    let legacy_config = CONFIG_V0_1.load(storage)?;
+    let config = Config {
+        native_denom: legacy_config.native_denom,
+        royalty: legacy_config.royalty,
+        protocol_fee_bps: 0,
+        fee_collector: state.owner.unwrap_or(state.collection_fabric_address),
+        strict_collections: false,
+        anti_sniping: None,
+    };
+    CONFIG.save(storage, &config)?;
+
+    // Listings of 0.1.0 were priced in the native denom
+    let legacy_listings = LISTINGS_V0_1
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (_, legacy) in legacy_listings {
+        let listing = Listing {
+            seller: legacy.seller,
+            collection: legacy.collection,
+            token_id: legacy.token_id,
+            price: legacy.price,
+            denom: Denom::Native(config.native_denom.clone()),
+            expires_at: None,
+            reserved_for: None,
+        };
+        let key = (listing.collection.as_str(), listing.token_id.as_str());
+        listings().replace(storage, key, Some(&listing), None)?;
+    }
+
+    // Auctions of 0.1.0 were English auctions without reserve, extension or buy-now price
+    for (_, legacy) in legacy_auctions {
+        let auction = Auction {
+            seller: legacy.seller,
+            collection: legacy.collection,
+            token_id: legacy.token_id,
+            start_price: legacy.start_price,
+            min_bid_step: legacy.min_bid_step,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            current_price: legacy.current_price,
+            current_bidder: legacy.current_bidder,
+            status: legacy.status,
+            kind: AuctionKind::English,
+            extension: None,
+            reserve_price: None,
+            reserve_hidden: false,
+            reserve_met: None,
+            buy_now_price: None,
+        };
+        let key = (auction.collection.as_str(), auction.token_id.as_str());
+        auctions().replace(storage, key, Some(&auction), None)?;
+    }
+
+    // Claiming an auction in 0.1.0 kept its deposit, which would block listing the NFT again
+    let deposits = DEPOSITS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, deposit)| deposit))
+        .collect::<StdResult<Vec<_>>>()?;
+    for deposit in deposits {
+        if !is_held(storage, &deposit)? {
+            let key = (
+                deposit.collection.as_str(),
+                deposit.owner.as_str(),
+                deposit.token_id.as_str(),
+            );
+            DEPOSITS.remove(storage, key);
+        }
+    }
+
+    for admin in ADMINS_V0_1.may_load(storage)?.unwrap_or_default() {
+        ADMINS.save(storage, &admin, &())?;
+    }
+    ADMINS_V0_1.remove(storage);
+
+    Ok(())
+}
+
+fn is_open(status: &AuctionStatus) -> bool {
+    !matches!(status, AuctionStatus::Ended | AuctionStatus::Cancelled)
+}
+
+/// Whether the marketplace still holds the NFT of a deposit for a listing, bundle or auction.
+fn is_held(storage: &dyn Storage, deposit: &Deposits) -> StdResult<bool> {
+    let key = (deposit.collection.as_str(), deposit.token_id.as_str());
+    let listed = listings()
+        .may_load(storage, key)?
+        .is_some_and(|listing| listing.seller == deposit.owner);
+    let auctioned = auctions()
+        .may_load(storage, key)?
+        .is_some_and(|auction| auction.seller == deposit.owner && is_open(&auction.status));
+    if listed || auctioned {
+        return Ok(true);
+    }
+    let item = (deposit.collection.clone(), deposit.token_id.clone());
+    for bundle in BUNDLES.range(storage, None, None, Order::Ascending) {
+        let (_, bundle) = bundle?;
+        if bundle.seller == deposit.owner && bundle.items.contains(&item) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -1,28 +1,47 @@
+use crate::state::{AntiSniping, AuctionStatus, PriceCurve, TradingOperation};
 use cosmwasm_std::{Addr, Uint128, Uint64};
+use cw20::{Cw20ReceiveMsg, Denom};
 use cw721::Cw721ReceiveMsg;
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 // Define the contract's messages
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum ExecuteMsg {
-    ListNftForSale(Cw721ReceiveMsg),
+    /// Lists an NFT sent to the marketplace with `SendNft`, the hook message carries the terms
+    #[serde(rename = "receive_nft")]
+    ReceiveNft(Cw721ReceiveMsg),
     CancelListing {
         collection: String,
         token_id: String,
     },
+    /// Changes the price of an active listing without transferring the NFT back
+    UpdateListingPrice {
+        collection: String,
+        token_id: String,
+        price: Uint128,
+    },
+    /// Returns up to `limit` expired listings to their sellers, callable by anyone
+    ReturnExpiredListings {
+        limit: Option<u32>,
+    },
     BuyNft {
         collection: String,
         token_id: String,
     },
     BuyBatch {
         asks: Vec<(String, String)>,
+        /// Skip listings that are gone and refund their share instead of failing
+        #[serde(default)]
+        allow_partial: bool,
     },
-    ListNftForAuction(Cw721ReceiveMsg),
+    /// Marks an auction as started. Optional, auctions go live at their start time.
     StartAuction {
         collection: String,
         token_id: String,
     },
+    /// Returns the NFT of an auction that hasn't started to the seller
     CancelAuction {
         collection: String,
         token_id: String,
@@ -36,12 +55,145 @@ pub enum ExecuteMsg {
         collection: String,
         token_id: String,
     },
+    /// Buys the NFT of a Dutch auction at its current price, overpayment is refunded
+    BuyDutchAuction {
+        collection: String,
+        token_id: String,
+    },
+    /// Ends an English auction by paying its buy-now price, the highest bid is refunded
+    BuyNow {
+        collection: String,
+        token_id: String,
+    },
     AddAdmin {
         account_id: Addr,
     },
     RemoveAdmin {
         account_id: Addr,
     },
+    /// Stops the given trading operations, owner or admins only
+    Pause {
+        operations: Vec<TradingOperation>,
+    },
+    /// Resumes the given trading operations, owner or admins only
+    Unpause {
+        operations: Vec<TradingOperation>,
+    },
+    /// Proposes a new owner, who has to accept before `expiry`. Replaces any pending proposal.
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    /// Called by the proposed owner to take over ownership
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    /// Leaves the contract without an owner, owner-gated operations can no longer be called
+    RenounceOwnership {},
+    /// Entry point for CW20 payments, see `Cw20HookMsg`
+    #[serde(rename = "receive")]
+    Receive(Cw20ReceiveMsg),
+    /// Changes the marketplace parameters, owner only. Fields left empty are kept.
+    /// Listings priced in the previous native denom keep it and can only be cancelled.
+    /// Auctions are paid in the current native denom, so it can't change while bids are escrowed.
+    UpdateConfig {
+        native_denom: Option<String>,
+        /// Royalty percentage paid to the collection fabric for collections without a royalty
+        royalty: Option<u128>,
+        /// Marketplace fee in basis points of the sale price, at most 1000 (10%)
+        protocol_fee_bps: Option<u64>,
+        fee_collector: Option<String>,
+        collection_fabric_address: Option<String>,
+        /// Restricts listing to registered collections
+        strict_collections: Option<bool>,
+    },
+    /// Sets the anti-sniping extension of English auctions listed without their own, owner only
+    SetDefaultAntiSniping {
+        anti_sniping: Option<AntiSniping>,
+    },
+    AddCw20Token {
+        token: String,
+    },
+    RemoveCw20Token {
+        token: String,
+    },
+    /// Sets the royalty paid on a collection that doesn't implement CW-2981, owner only
+    SetCollectionRoyalty {
+        collection: String,
+        recipient: String,
+        /// Percentage of the sale price
+        royalty: Uint128,
+    },
+    RemoveCollectionRoyalty {
+        collection: String,
+    },
+    /// Adds a collection to the registry or updates its metadata, owner or admins only
+    RegisterCollection {
+        collection: String,
+        name: String,
+        creator: String,
+        verified: bool,
+    },
+    /// Removes a collection from the registry, owner or admins only
+    UnregisterCollection {
+        collection: String,
+    },
+    /// Escrows the attached funds as an offer on any NFT, listed or not
+    MakeOffer {
+        collection: String,
+        token_id: String,
+        price: Uint128,
+        /// Block time in seconds from which the offer can no longer be accepted
+        expires: Uint64,
+    },
+    /// Withdraws the sender's own offer and refunds it
+    CancelOffer {
+        collection: String,
+        token_id: String,
+    },
+    /// Refunds an offer, callable by its bidder or by anyone once it has expired
+    RetractOffer {
+        collection: String,
+        token_id: String,
+        bidder: String,
+    },
+    /// Sells the NFT into an offer, callable by its owner or the seller of its listing
+    AcceptOffer {
+        collection: String,
+        token_id: String,
+        bidder: String,
+    },
+    /// Escrows `price * quantity` to buy up to `quantity` tokens of any holder of the collection
+    MakeCollectionOffer {
+        collection: String,
+        /// Price paid per token
+        price: Uint128,
+        quantity: u32,
+        expires: Uint64,
+    },
+    /// Withdraws the sender's collection offer and refunds the unfilled part
+    CancelCollectionOffer {
+        collection: String,
+    },
+    /// Sells one token into a collection offer
+    AcceptCollectionOffer {
+        collection: String,
+        token_id: String,
+        bidder: String,
+    },
+    /// Escrows several NFTs, possibly from different collections, sold together at one price
+    ListBundle {
+        /// (collection, token_id) pairs, each approved for the marketplace
+        items: Vec<(String, String)>,
+        price: Uint128,
+        denom: Option<Denom>,
+    },
+    /// Returns every NFT of the bundle to its seller
+    CancelBundle {
+        bundle_id: u64,
+    },
+    BuyBundle {
+        bundle_id: u64,
+    },
 }
 
 // Define the contract's query messages
@@ -53,15 +205,121 @@ pub enum QueryMsg {
         token_id: String,
     },
     GetAuctionCount {},
+    /// Native funds escrowed for the highest bid of an auction
+    GetAuctionEscrow {
+        collection: String,
+        token_id: String,
+    },
+    /// Funds escrowed by auction bids and native offers against the marketplace balance of the
+    /// native denom
+    GetEscrowInvariant {},
     GetAuctionByCollectionTokenID {
         collection: String,
         token_id: String,
     },
+    /// Price a buyer pays for the NFT of a Dutch auction at the current block time
+    GetCurrentDutchPrice {
+        collection: String,
+        token_id: String,
+    },
     IsAdmin {
         account_id: Addr,
     },
+    GetOwnership {},
+    /// Trading operations that are currently paused
+    GetPauseState {},
+    GetConfig {},
+    GetCw20Tokens {},
+    /// Marketplace fee and fallback royalty taken on sales
+    GetFeeSchedule {},
+    /// Royalty set on the marketplace for a collection without CW-2981 support
+    GetCollectionRoyalty {
+        collection: String,
+    },
+    /// Registry entry of a collection
+    GetCollection {
+        collection: String,
+    },
+    /// Registered collections, optionally only the verified ones
+    ListCollections {
+        #[serde(default)]
+        verified_only: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Royalty recipient and amount owed on a sale of the token at `sale_price`
+    GetRoyaltyInfo {
+        collection: String,
+        token_id: String,
+        sale_price: Uint128,
+    },
+    GetOffersByToken {
+        collection: String,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetOffersByBidder {
+        bidder: String,
+        /// (collection, token_id) of the last offer of the previous page
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Highest live collection offer in `denom`, the native denom by default
+    GetBestCollectionOffer {
+        collection: String,
+        denom: Option<Denom>,
+    },
+    /// Active listings, optionally restricted to one collection
+    ListListings {
+        collection: Option<String>,
+        /// (collection, token_id) of the last listing of the previous page
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Auctions, optionally restricted to one collection and status
+    ListAuctions {
+        collection: Option<String>,
+        status: Option<AuctionStatus>,
+        /// (collection, token_id) of the last auction of the previous page
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    ListingsBySeller {
+        seller: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Private listings reserved for `buyer`
+    PrivateListings {
+        buyer: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    GetBundle {
+        bundle_id: u64,
+    },
+    ListBundles {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    AuctionsBySeller {
+        seller: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Auctions in which `bidder` holds the highest bid. Auctions keep their last bidder once
+    /// they end, so ended, claimed and reserve-refunded auctions are included, see `status`.
+    AuctionsByBidder {
+        bidder: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 // Define the InstantiateMsg
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -69,31 +327,137 @@ pub struct InstantiateMsg {
     pub collection_fabric_address: String, // The address of the collection fabric contract
     pub native_denom: String,
     pub royalty: u128,
+    /// Marketplace fee in basis points of the sale price
+    #[serde(default)]
+    pub protocol_fee_bps: u64,
+    /// Receives the marketplace fee, defaults to the owner
+    pub fee_collector: Option<String>,
+    /// CW20 contracts accepted as payment for listings
+    #[serde(default)]
+    pub cw20_tokens: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum ListingHookMsg {
+pub enum Cw721HookMsg {
     SetListing {
-        owner: String,
+        price: Uint128,
+        /// Payment asset of the listing, defaults to the native denom
+        denom: Option<Denom>,
+        /// Block time in seconds from which the listing can no longer be bought
+        expires_at: Option<Uint64>,
+        /// Makes the listing private, only this address can buy it
+        reserved_for: Option<String>,
+    },
+    SetAuctionListing {
+        start_price: Uint128,
+        min_bid_step: Uint128,
+        start_time: Uint64,
+        end_time: Uint64,
+        /// Overrides the marketplace default anti-sniping extension
+        #[serde(default)]
+        anti_sniping: Option<AntiSniping>,
+        /// Lowest bid the NFT is sold for
+        #[serde(default)]
+        reserve_price: Option<Uint128>,
+        /// Keeps the reserve price out of the auction queries
+        #[serde(default)]
+        hide_reserve_price: bool,
+        /// Price at which the NFT can be bought without bidding
+        #[serde(default)]
+        buy_now_price: Option<Uint128>,
+    },
+    SetDutchAuctionListing {
+        start_price: Uint128,
+        floor_price: Uint128,
+        start_time: Uint64,
+        end_time: Uint64,
+        curve: PriceCurve,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    BuyNft {
+        collection: String,
+        token_id: String,
+    },
+    BuyBatch {
+        asks: Vec<(String, String)>,
+        /// Skip listings that are gone and refund their share instead of failing
+        #[serde(default)]
+        allow_partial: bool,
+    },
+    BuyBundle {
+        bundle_id: u64,
+    },
+    MakeOffer {
         collection: String,
         token_id: String,
+        expires: Uint64,
+    },
+    /// The amount sent must equal `price * quantity`
+    MakeCollectionOffer {
+        collection: String,
         price: Uint128,
-        royalty: Uint128,
+        quantity: u32,
+        expires: Uint64,
     },
 }
 
+/// Royalty query of CW-2981 collections
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum AuctionListingHookMsg {
-    SetAuctionListing {
-        owner: String,
-        collection: String,
+pub enum Cw2981QueryMsg {
+    RoyaltyInfo {
         token_id: String,
-        start_price: Uint128,
-        min_bid_step: Uint128,
-        start_time: Uint64,
-        end_time: Uint64,
-        royalty: Uint128,
+        sale_price: Uint128,
     },
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipResponse {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowInvariantResponse {
+    pub denom: String,
+    pub auction_escrow: Uint128,
+    /// Escrow of item and collection offers paid in the native denom.
+    pub offer_escrow: Uint128,
+    pub total_escrow: Uint128,
+    pub balance: Uint128,
+    /// The balance covers every escrowed bid and offer.
+    pub holds: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Option<Addr>,
+    pub native_denom: String,
+    pub royalty: u128,
+    pub protocol_fee_bps: u64,
+    pub fee_collector: Addr,
+    pub collection_fabric_address: Addr,
+    pub strict_collections: bool,
+    pub anti_sniping: Option<AntiSniping>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeScheduleResponse {
+    pub protocol_fee_bps: u64,
+    pub fee_collector: Addr,
+    /// Royalty percentage paid when a collection has neither CW-2981 nor a default royalty
+    pub default_royalty: u128,
+    pub default_royalty_recipient: Addr,
+}
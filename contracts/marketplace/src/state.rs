@@ -1,18 +1,32 @@
 use cosmwasm_std::{Addr, Uint128, Uint64};
+use cw20::Denom;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cw_storage_plus::{Item, Map};
 
 // Define the contract's state
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub native_denom: String,
-    pub royalty: u128
+    /// Royalty percentage paid to the collection fabric for collections without a royalty.
+    pub royalty: u128,
+    /// Marketplace fee in basis points of the sale price.
+    pub protocol_fee_bps: u64,
+    /// Receives the marketplace fee.
+    pub fee_collector: Addr,
+    /// Only collections in the registry can be listed.
+    #[serde(default)]
+    pub strict_collections: bool,
+    /// Anti-sniping extension of English auctions listed without their own.
+    #[serde(default)]
+    pub anti_sniping: Option<AntiSniping>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    pub owner: Addr,
+    /// None once ownership has been renounced.
+    pub owner: Option<Addr>,
     pub collection_fabric_address: Addr,
     pub listing_count: u128,
     pub auction_count: u128,
@@ -25,17 +39,21 @@ pub struct Listing {
     pub collection: String,
     pub token_id: String,
     pub price: Uint128,
-    /// Royalty of the listing, set automatically by deriving the value from the collection contract.
-    pub royalty: Uint128,
+    /// Asset the listing is priced in, either the native denom or an accepted CW20 token.
+    pub denom: Denom,
+    /// Block time in seconds from which the listing can no longer be bought.
+    pub expires_at: Option<Uint64>,
+    /// Only this address can buy the listing when set.
+    pub reserved_for: Option<Addr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum AuctionStatus {
-    /// The auction is waiting for auction.
+    /// The auction is waiting for its start time.
     WaitingAuction,
-    /// The auction is in auction.
+    /// The auction is taking bids.
     InAuction,
-    /// The auction is waiting for claim.
+    /// The auction has passed its end time and is waiting for claim.
     WaitingForClaim,
     /// The auction is ended.
     Ended,
@@ -56,7 +74,106 @@ pub struct Auction {
     pub current_price: Uint128,
     pub current_bidder: Option<Addr>,
     pub status: AuctionStatus,
-    pub royalty: Uint128,
+    #[serde(default)]
+    pub kind: AuctionKind,
+    #[serde(default)]
+    pub extension: Option<AuctionExtension>,
+    /// Lowest winning bid, below it the NFT goes back to the seller when the auction ends.
+    #[serde(default)]
+    pub reserve_price: Option<Uint128>,
+    /// Leaves the reserve price out of query responses. It can still be read from the raw
+    /// contract storage.
+    #[serde(default)]
+    pub reserve_hidden: bool,
+    /// Whether the winning bid met the reserve price, set once the auction is claimed.
+    #[serde(default)]
+    pub reserve_met: Option<bool>,
+    /// Price at which the NFT can be bought right away, ending the auction.
+    #[serde(default)]
+    pub buy_now_price: Option<Uint128>,
+}
+
+/// Bids placed less than `extension_window` seconds before the end of an English auction push
+/// its end to `extension_duration` seconds after the bid.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AntiSniping {
+    pub extension_window: Uint64,
+    pub extension_duration: Uint64,
+    /// Most seconds bids can add past the end time the auction was listed with.
+    pub max_extension: Option<Uint64>,
+}
+
+/// Anti-sniping extension of an auction, with the cap resolved when it was listed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionExtension {
+    pub extension_window: Uint64,
+    pub extension_duration: Uint64,
+    /// Latest end time bids can push the auction to.
+    pub max_end_time: Option<Uint64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub enum AuctionKind {
+    /// Ascending bids, the highest bidder claims the NFT once the auction ends.
+    #[default]
+    English,
+    /// The price falls from `start_price` to `floor_price` between `start_time` and `end_time`,
+    /// the first buyer pays the current price.
+    Dutch {
+        floor_price: Uint128,
+        curve: PriceCurve,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum PriceCurve {
+    /// The price falls every second.
+    Linear,
+    /// The price falls once every `interval` seconds.
+    Stepped { interval: Uint64 },
+}
+
+impl Auction {
+    /// Status of the auction at block time `now`. Auctions go live at their start time and wait
+    /// for claim after their end time, whether or not they were started.
+    pub fn effective_status(&self, now: Uint64) -> AuctionStatus {
+        match self.status {
+            AuctionStatus::Ended | AuctionStatus::Cancelled => self.status.clone(),
+            _ if now < self.start_time => AuctionStatus::WaitingAuction,
+            _ if now < self.end_time => AuctionStatus::InAuction,
+            _ => AuctionStatus::WaitingForClaim,
+        }
+    }
+
+    /// The auction as shown in query responses at block time `now`.
+    pub fn public(mut self, now: Uint64) -> Self {
+        self.status = self.effective_status(now);
+        if self.reserve_hidden {
+            self.reserve_price = None;
+        }
+        self
+    }
+
+    /// Price of a Dutch auction at block time `now`, None for English auctions.
+    pub fn dutch_price(&self, now: Uint64) -> Option<Uint128> {
+        let AuctionKind::Dutch { floor_price, curve } = &self.kind else {
+            return None;
+        };
+        if now <= self.start_time {
+            return Some(self.start_price);
+        }
+        if now >= self.end_time {
+            return Some(*floor_price);
+        }
+
+        let mut elapsed = (now - self.start_time).u64();
+        if let PriceCurve::Stepped { interval } = curve {
+            elapsed -= elapsed % interval.u64();
+        }
+        let duration = (self.end_time - self.start_time).u64();
+        let drop = (self.start_price - floor_price).multiply_ratio(elapsed, duration);
+        Some(self.start_price - drop)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -66,11 +183,254 @@ pub struct Deposits {
     pub token_id: String,
 }
 
+// Define the Offer struct
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub bidder: Addr,
+    pub collection: String,
+    pub token_id: String,
+    /// Amount escrowed by the marketplace for this offer.
+    pub price: Uint128,
+    pub denom: Denom,
+    /// Block time in seconds from which the offer can no longer be accepted.
+    pub expires: Uint64,
+}
+
+pub struct OfferIndexes<'a> {
+    pub bidder: MultiIndex<'a, Addr, Offer, (String, String, Addr)>,
+}
+
+impl<'a> IndexList<Offer> for OfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Offer>> + '_> {
+        let v: Vec<&dyn Index<Offer>> = vec![&self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+pub struct ListingIndexes<'a> {
+    pub seller: MultiIndex<'a, String, Listing, (String, String)>,
+    /// Listings without expiry are indexed at `u64::MAX`.
+    pub expiry: MultiIndex<'a, u64, Listing, (String, String)>,
+    /// Public listings are indexed under an empty buyer.
+    pub reserved_for: MultiIndex<'a, String, Listing, (String, String)>,
+}
+
+impl<'a> IndexList<Listing> for ListingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Listing>> + '_> {
+        let v: Vec<&dyn Index<Listing>> = vec![&self.seller, &self.expiry, &self.reserved_for];
+        Box::new(v.into_iter())
+    }
+}
+
+pub struct AuctionIndexes<'a> {
+    pub seller: MultiIndex<'a, String, Auction, (String, String)>,
+    /// Auctions without bids are indexed under an empty bidder.
+    pub bidder: MultiIndex<'a, String, Auction, (String, String)>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![&self.seller, &self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+// Define the Bundle struct
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bundle {
+    pub id: u64,
+    pub seller: String,
+    /// (collection, token_id) pairs sold together.
+    pub items: Vec<(String, String)>,
+    pub price: Uint128,
+    pub denom: Denom,
+}
+
+// Define the CollectionOffer struct
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionOffer {
+    pub bidder: Addr,
+    pub collection: String,
+    /// Price paid for each token, `price * quantity` is held in escrow.
+    pub price: Uint128,
+    pub denom: Denom,
+    /// Number of tokens the bidder still wants to buy.
+    pub quantity: u32,
+    /// Block time in seconds from which the offer can no longer be accepted.
+    pub expires: Uint64,
+}
+
+pub struct CollectionOfferIndexes<'a> {
+    pub price: MultiIndex<'a, (String, u128), CollectionOffer, (String, Addr)>,
+}
+
+impl<'a> IndexList<CollectionOffer> for CollectionOfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollectionOffer>> + '_> {
+        let v: Vec<&dyn Index<CollectionOffer>> = vec![&self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Trading operations that can be paused independently. Cancels and withdrawals are never paused.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum TradingOperation {
+    /// Listing NFTs for sale, in auctions or in bundles, and starting auctions.
+    Listing,
+    /// Buying listings and bundles, and accepting offers.
+    Buying,
+    /// Bidding in auctions and making offers.
+    Bidding,
+    /// Claiming ended auctions.
+    Claiming,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseState {
+    pub listing: bool,
+    pub buying: bool,
+    pub bidding: bool,
+    pub claiming: bool,
+}
+
+impl PauseState {
+    pub fn is_paused(&self, operation: TradingOperation) -> bool {
+        match operation {
+            TradingOperation::Listing => self.listing,
+            TradingOperation::Buying => self.buying,
+            TradingOperation::Bidding => self.bidding,
+            TradingOperation::Claiming => self.claiming,
+        }
+    }
+
+    pub fn set(&mut self, operation: TradingOperation, paused: bool) {
+        match operation {
+            TradingOperation::Listing => self.listing = paused,
+            TradingOperation::Buying => self.buying = paused,
+            TradingOperation::Bidding => self.bidding = paused,
+            TradingOperation::Claiming => self.claiming = paused,
+        }
+    }
+}
+
+/// Owner proposed by the current owner, who becomes owner once they accept.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+/// Registry entry of a collection approved for the storefront.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionInfo {
+    pub collection: Addr,
+    pub name: String,
+    pub creator: Addr,
+    pub verified: bool,
+}
+
+/// Royalty paid on sales of a collection that doesn't implement CW-2981.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionRoyalty {
+    pub recipient: Addr,
+    /// Percentage of the sale price.
+    pub royalty: Uint128,
+}
+
 // Define the storage keys
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const STATE: Item<State> = Item::new("state");
-pub const LISTINGS: Map<(&str, &str), Listing> = Map::new("listings");
-pub const AUCTIONS: Map<(&str, &str), Auction> = Map::new("auctions");
-pub const ADMINS: Item<Vec<Addr>> = Item::new("admins");
+pub const ADMINS: Map<&Addr, ()> = Map::new("admin_set");
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+pub const PAUSE_STATE: Item<PauseState> = Item::new("pause_state");
+pub const CW20_TOKENS: Map<&Addr, ()> = Map::new("cw20_tokens");
+pub const BUNDLES: Map<u64, Bundle> = Map::new("bundles");
+pub const BUNDLE_SEQ: Item<u64> = Item::new("bundle_seq");
+pub const COLLECTION_ROYALTIES: Map<&str, CollectionRoyalty> = Map::new("collection_royalties");
+pub const COLLECTIONS: Map<&Addr, CollectionInfo> = Map::new("collections");
 //contract, owner, token_id
 pub const DEPOSITS: Map<(&str, &str, &str), Deposits> = Map::new("deposits");
+// collection, token_id -> native funds escrowed for the highest bid
+pub const AUCTION_ESCROW: Map<(&str, &str), Uint128> = Map::new("auction_escrow");
+pub const TOTAL_AUCTION_ESCROW: Item<Uint128> = Item::new("total_auction_escrow");
+// native denom -> funds escrowed by item and collection offers paid in it
+pub const NATIVE_OFFER_ESCROW: Map<&str, Uint128> = Map::new("native_offer_escrow");
+
+//contract, token_id, bidder
+pub fn offers<'a>() -> IndexedMap<'a, (&'a str, &'a str, &'a Addr), Offer, OfferIndexes<'a>> {
+    let indexes = OfferIndexes {
+        bidder: MultiIndex::new(
+            |offer: &Offer| offer.bidder.clone(),
+            "offers",
+            "offers__bidder",
+        ),
+    };
+    IndexedMap::new("offers", indexes)
+}
+
+//contract, bidder
+pub fn collection_offers<'a>(
+) -> IndexedMap<'a, (&'a str, &'a Addr), CollectionOffer, CollectionOfferIndexes<'a>> {
+    let indexes = CollectionOfferIndexes {
+        price: MultiIndex::new(
+            |offer: &CollectionOffer| (offer.collection.clone(), offer.price.u128()),
+            "collection_offers",
+            "collection_offers__price",
+        ),
+    };
+    IndexedMap::new("collection_offers", indexes)
+}
+
+//contract, token_id
+pub fn listings<'a>() -> IndexedMap<'a, (&'a str, &'a str), Listing, ListingIndexes<'a>> {
+    let indexes = ListingIndexes {
+        seller: MultiIndex::new(
+            |listing: &Listing| listing.seller.clone(),
+            "listings",
+            "listings__seller",
+        ),
+        expiry: MultiIndex::new(
+            |listing: &Listing| {
+                listing
+                    .expires_at
+                    .map_or(u64::MAX, |expires_at| expires_at.u64())
+            },
+            "listings",
+            "listings__expiry",
+        ),
+        reserved_for: MultiIndex::new(
+            |listing: &Listing| {
+                listing
+                    .reserved_for
+                    .as_ref()
+                    .map(Addr::to_string)
+                    .unwrap_or_default()
+            },
+            "listings",
+            "listings__reserved_for",
+        ),
+    };
+    IndexedMap::new("listings", indexes)
+}
+
+//contract, token_id
+pub fn auctions<'a>() -> IndexedMap<'a, (&'a str, &'a str), Auction, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        seller: MultiIndex::new(
+            |auction: &Auction| auction.seller.clone(),
+            "auctions",
+            "auctions__seller",
+        ),
+        bidder: MultiIndex::new(
+            |auction: &Auction| {
+                auction
+                    .current_bidder
+                    .as_ref()
+                    .map(Addr::to_string)
+                    .unwrap_or_default()
+            },
+            "auctions",
+            "auctions__bidder",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
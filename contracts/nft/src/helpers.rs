@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
+    to_json_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
 };
 
 //use crate::msg::{ExecuteMsg, GetCountResponse, QueryMsg};
@@ -23,7 +23,7 @@ impl NftContract {
     }
 
     pub fn call<T: Into<Cw721ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
-        let msg = to_binary(&msg.into())?;
+        let msg = to_json_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
@@ -40,12 +40,12 @@ impl NftContract {
         CQ: CustomQuery,
     {
         let msg = QueryMsg::OwnerOf {
-            token_id: token_id,
+            token_id,
             include_expired: None,
         };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: OwnerOfResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
@@ -65,7 +65,7 @@ impl NftContract {
         };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: TokensResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;